@@ -0,0 +1,36 @@
+#![no_main]
+
+//! Feeds arbitrary sequences of packets and commands to a `RustDoIt` drone,
+//! with real channels, including the packets drained when the drone crashes
+
+use std::collections::HashMap;
+use crossbeam_channel::unbounded;
+use libfuzzer_sys::fuzz_target;
+use rust_do_it::RustDoIt;
+use rust_do_it_fuzz::{FuzzInput, DRONE_ID, NEIGHBOURS};
+use wg_2024::drone::Drone;
+
+fuzz_target!(|inputs: Vec<FuzzInput>| {
+    let (controller_send, _event_recv) = unbounded();
+    let (_command_send, controller_recv) = unbounded();
+    let (packet_send, packet_recv) = unbounded();
+    // every neighbour shares the same channel, the fuzzer only cares about the drone
+    let (neighbour_send, _neighbour_recv) = unbounded();
+
+    let senders = NEIGHBOURS
+        .iter()
+        .map(|id| (*id, neighbour_send.clone()))
+        .collect::<HashMap<_, _>>();
+    let mut drone = RustDoIt::new(DRONE_ID, controller_send, controller_recv, packet_recv, senders, 0.5)
+        .with_seed(0);
+
+    for input in inputs {
+        match input {
+            FuzzInput::Queue(packet) => {
+                packet_send.send(packet.into()).ok();
+            },
+            FuzzInput::Packet(packet) => drone.handle_packet(packet.into()),
+            FuzzInput::Command(command) => drone.handle_command(command.into_command(neighbour_send.clone())),
+        }
+    }
+});
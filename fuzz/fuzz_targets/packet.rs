@@ -0,0 +1,16 @@
+#![no_main]
+
+//! Feeds arbitrary packets to the decision logic of the drone.
+//! The core must never panic, whatever the routing header looks like
+
+use libfuzzer_sys::fuzz_target;
+use rust_do_it::DroneCore;
+use rust_do_it_fuzz::{FuzzPacket, DRONE_ID, NEIGHBOURS};
+
+fuzz_target!(|input: (f32, Vec<FuzzPacket>)| {
+    let (pdr, packets) = input;
+    let mut core = DroneCore::new(DRONE_ID, NEIGHBOURS, pdr).with_seed(0);
+    for packet in packets {
+        core.handle_packet(packet.into());
+    }
+});
@@ -0,0 +1,170 @@
+//! Conversions from the arbitrary input of the fuzzer to `wg_2024` packets and commands.
+//!
+//! The `wg_2024` types do not implement `Arbitrary`, so the fuzzer generates the
+//! mirror types defined here and converts them. Node ids are folded into a small
+//! range, so that the generated routes often go through the fuzzed drone and its
+//! neighbours instead of being rejected at the first check.
+
+use arbitrary::Arbitrary;
+use crossbeam_channel::Sender;
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Ack, FloodRequest, FloodResponse, Fragment, Nack, NackType, NodeType, Packet, PacketType};
+
+/// Id of the fuzzed drone
+pub const DRONE_ID: NodeId = 1;
+
+/// Ids of the neighbours of the fuzzed drone when the target starts
+pub const NEIGHBOURS: [NodeId; 3] = [0, 2, 3];
+
+/// Node ids are folded into `0..NODE_RANGE`
+const NODE_RANGE: NodeId = 8;
+
+fn node(id: NodeId) -> NodeId {
+    id % NODE_RANGE
+}
+
+#[derive(Debug, Arbitrary)]
+pub struct FuzzHeader {
+    hops: Vec<NodeId>,
+    hop_index: u8,
+    /// Moves the hop index close to `usize::MAX`
+    far_index: bool,
+}
+
+impl From<FuzzHeader> for SourceRoutingHeader {
+    fn from(header: FuzzHeader) -> Self {
+        let hop_index = if header.far_index {
+            usize::MAX - header.hop_index as usize
+        } else {
+            header.hop_index as usize
+        };
+        SourceRoutingHeader::new(header.hops.into_iter().map(node).collect(), hop_index)
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum FuzzNodeType {
+    Client,
+    Drone,
+    Server,
+}
+
+impl From<FuzzNodeType> for NodeType {
+    fn from(node_type: FuzzNodeType) -> Self {
+        match node_type {
+            FuzzNodeType::Client => NodeType::Client,
+            FuzzNodeType::Drone => NodeType::Drone,
+            FuzzNodeType::Server => NodeType::Server,
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum FuzzNackType {
+    ErrorInRouting(NodeId),
+    DestinationIsDrone,
+    Dropped,
+    UnexpectedRecipient(NodeId),
+}
+
+impl From<FuzzNackType> for NackType {
+    fn from(nack_type: FuzzNackType) -> Self {
+        match nack_type {
+            FuzzNackType::ErrorInRouting(id) => NackType::ErrorInRouting(id),
+            FuzzNackType::DestinationIsDrone => NackType::DestinationIsDrone,
+            FuzzNackType::Dropped => NackType::Dropped,
+            FuzzNackType::UnexpectedRecipient(id) => NackType::UnexpectedRecipient(id),
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+pub enum FuzzPacketType {
+    MsgFragment { fragment_index: u64, total_n_fragments: u64, length: u8, data: [u8; 128] },
+    Ack { fragment_index: u64 },
+    Nack { fragment_index: u64, nack_type: FuzzNackType },
+    FloodRequest { flood_id: u64, initiator_id: NodeId, path_trace: Vec<(NodeId, FuzzNodeType)> },
+    FloodResponse { flood_id: u64, path_trace: Vec<(NodeId, FuzzNodeType)> },
+}
+
+fn path_trace(path_trace: Vec<(NodeId, FuzzNodeType)>) -> Vec<(NodeId, NodeType)> {
+    path_trace
+        .into_iter()
+        .map(|(id, node_type)| (node(id), node_type.into()))
+        .collect()
+}
+
+impl From<FuzzPacketType> for PacketType {
+    fn from(pack_type: FuzzPacketType) -> Self {
+        match pack_type {
+            FuzzPacketType::MsgFragment { fragment_index, total_n_fragments, length, data } => {
+                PacketType::MsgFragment(Fragment { fragment_index, total_n_fragments, length, data })
+            },
+            FuzzPacketType::Ack { fragment_index } => PacketType::Ack(Ack { fragment_index }),
+            FuzzPacketType::Nack { fragment_index, nack_type } => PacketType::Nack(Nack {
+                fragment_index,
+                nack_type: nack_type.into(),
+            }),
+            FuzzPacketType::FloodRequest { flood_id, initiator_id, path_trace: trace } => {
+                PacketType::FloodRequest(FloodRequest {
+                    flood_id,
+                    initiator_id: node(initiator_id),
+                    path_trace: path_trace(trace),
+                })
+            },
+            FuzzPacketType::FloodResponse { flood_id, path_trace: trace } => {
+                PacketType::FloodResponse(FloodResponse { flood_id, path_trace: path_trace(trace) })
+            },
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+pub struct FuzzPacket {
+    routing_header: FuzzHeader,
+    session_id: u64,
+    pack_type: FuzzPacketType,
+}
+
+impl From<FuzzPacket> for Packet {
+    fn from(packet: FuzzPacket) -> Self {
+        Packet {
+            routing_header: packet.routing_header.into(),
+            session_id: packet.session_id,
+            pack_type: packet.pack_type.into(),
+        }
+    }
+}
+
+/// A command for the drone. `AddSender` carries only the id, the target
+/// provides the channel
+#[derive(Debug, Arbitrary)]
+pub enum FuzzCommand {
+    AddSender(NodeId),
+    RemoveSender(NodeId),
+    SetPacketDropRate(f32),
+    Crash,
+}
+
+impl FuzzCommand {
+    /// Converts the command, using `sender` for `DroneCommand::AddSender`
+    pub fn into_command(self, sender: Sender<Packet>) -> DroneCommand {
+        match self {
+            FuzzCommand::AddSender(id) => DroneCommand::AddSender(node(id), sender),
+            FuzzCommand::RemoveSender(id) => DroneCommand::RemoveSender(node(id)),
+            FuzzCommand::SetPacketDropRate(pdr) => DroneCommand::SetPacketDropRate(pdr),
+            FuzzCommand::Crash => DroneCommand::Crash,
+        }
+    }
+}
+
+/// One input of the `commands` target
+#[derive(Debug, Arbitrary)]
+pub enum FuzzInput {
+    /// A packet put in the channel of the drone
+    Queue(FuzzPacket),
+    /// A packet handled right away
+    Packet(FuzzPacket),
+    Command(FuzzCommand),
+}
@@ -0,0 +1,213 @@
+use std::fmt;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use rust_do_it::network::{shell, DroneRegistry, Endpoints, Network, NetworkConfig, Shell};
+use rust_do_it::simulation::scenario::Scenario;
+
+mod repl;
+
+const USAGE: &str = "\
+Usage: rust_do_it [OPTIONS] [CONFIG]
+
+Runs RustDoIt drones on the topology described by CONFIG
+(default: src/config/config.toml), sends messages from every client
+to every server and prints what the drones did.
+With --repl the network is controlled interactively instead.
+With --scenario the scenario in FILE is run and checked instead.
+
+Options:
+    --messages <N>       number of messages to send (default: 100)
+    --duration <SECS>    keep sending messages for SECS seconds instead
+    --size <BYTES>       size of every message (default: 512)
+    --interval <MS>      pause between two messages (default: 1)
+    --repl               start an interactive shell instead of sending messages
+    --scenario <FILE>    run the scenario in FILE and exit with an error if it fails
+    --help               print this message";
+
+const DEFAULT_CONFIG: &str = "src/config/config.toml";
+
+/// How long the simulator waits for the acks and nacks once the traffic is over
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// When the traffic stops
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Limit {
+    Messages(u64),
+    Duration(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    config: String,
+    limit: Limit,
+    size: usize,
+    interval: Duration,
+    repl: bool,
+    scenario: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            config: DEFAULT_CONFIG.to_string(),
+            limit: Limit::Messages(100),
+            size: 512,
+            interval: Duration::from_millis(1),
+            repl: false,
+            scenario: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum CliError {
+    Help,
+    Usage(String),
+    Config(String),
+    /// The scenario ran but did not pass
+    Failed,
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Help => write!(f, "{}", USAGE),
+            CliError::Usage(message) => write!(f, "{}\n\n{}", message, USAGE),
+            CliError::Config(message) => write!(f, "{}", message),
+            CliError::Failed => write!(f, "the scenario failed"),
+        }
+    }
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, CliError> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next().ok_or_else(|| CliError::Usage(format!("missing value for {}", name)))
+        };
+        match arg.as_str() {
+            "--help" | "-h" => return Err(CliError::Help),
+            "--repl" => options.repl = true,
+            "--scenario" => options.scenario = Some(value(&arg)?),
+            "--messages" => options.limit = Limit::Messages(parse_number(&value(&arg)?, &arg)?),
+            "--duration" => options.limit = Limit::Duration(Duration::from_secs(parse_number(&value(&arg)?, &arg)?)),
+            "--size" => options.size = parse_number(&value(&arg)?, &arg)?,
+            "--interval" => options.interval = Duration::from_millis(parse_number(&value(&arg)?, &arg)?),
+            flag if flag.starts_with('-') => return Err(CliError::Usage(format!("unknown option {}", flag))),
+            config => options.config = config.to_string(),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, name: &str) -> Result<T, CliError> {
+    value.parse().map_err(|_| CliError::Usage(format!("invalid value {} for {}", value, name)))
+}
+
+fn load_network(path: &str) -> Result<Network, CliError> {
+    let toml = std::fs::read_to_string(path)
+        .map_err(|err| CliError::Config(format!("cannot read {}: {}", path, err)))?;
+    let config = NetworkConfig::from_toml(&toml)
+        .map_err(|err| CliError::Config(format!("cannot parse {}: {}", path, err)))?;
+    Network::start(&config, &DroneRegistry::default())
+        .map_err(|err| CliError::Config(format!("cannot start {}: {}", path, err)))
+}
+
+fn run_scenario(path: &str) -> Result<(), CliError> {
+    let toml = std::fs::read_to_string(path)
+        .map_err(|err| CliError::Config(format!("cannot read {}: {}", path, err)))?;
+    let scenario = Scenario::from_toml(&toml)
+        .map_err(|err| CliError::Config(format!("cannot parse {}: {}", path, err)))?;
+    let report = scenario.run();
+    println!("{}", report);
+    if report.passed() { Ok(()) } else { Err(CliError::Failed) }
+}
+
+fn run_traffic(network: &Network, endpoints: &mut Endpoints, options: &Options) {
+    // This function sends the messages, from every client
+    // to every server in turn, then waits for their acks and nacks
+    // ### Parameters:
+    // - `network`: The running network
+    // - `endpoints`: The clients and servers of the network
+    // - `options`: The limit, size and pace of the traffic
+
+    let clients = network.clients().collect::<Vec<_>>();
+    let servers = network.servers().collect::<Vec<_>>();
+    if clients.is_empty() || servers.is_empty() {
+        eprintln!("the topology needs at least a client and a server");
+        return;
+    }
+
+    let data = vec![b'x'; options.size];
+    let started = Instant::now();
+    let mut sent = 0;
+    loop {
+        let done = match options.limit {
+            Limit::Messages(messages) => sent >= messages,
+            Limit::Duration(duration) => started.elapsed() >= duration,
+        };
+        if done {
+            break;
+        }
+
+        let client = clients[sent as usize % clients.len()];
+        let server = servers[(sent as usize / clients.len()) % servers.len()];
+        endpoints.send(network, client, server, &data);
+        sent += 1;
+
+        endpoints.handle_events(network, &network.events());
+        endpoints.poll(network);
+        std::thread::sleep(options.interval);
+    }
+
+    let deadline = Instant::now() + SETTLE_TIMEOUT;
+    loop {
+        endpoints.handle_events(network, &network.events());
+        endpoints.poll(network);
+        let stats = endpoints.stats();
+        if stats.acks_received + stats.nacks_received >= stats.fragments_sent || !network.wait(deadline) {
+            break;
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+
+    let result = parse_options(std::env::args().skip(1)).and_then(|options| {
+        if let Some(scenario) = &options.scenario {
+            return run_scenario(scenario);
+        }
+        let network = load_network(&options.config)?;
+        if options.repl {
+            let mut shell = Shell::new(network);
+            repl::run(&mut shell);
+            shell.shutdown();
+            return Ok(());
+        }
+
+        let mut endpoints = Endpoints::new(&network);
+        run_traffic(&network, &mut endpoints, &options);
+        println!("{}", shell::stats_table(&network, &endpoints));
+        network.shutdown();
+        Ok(())
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Help) => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        },
+        Err(err @ CliError::Usage(_)) => {
+            eprintln!("{}", err);
+            ExitCode::from(2)
+        },
+        Err(CliError::Failed) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        },
+    }
+}
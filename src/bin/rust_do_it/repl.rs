@@ -0,0 +1,69 @@
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+use crossbeam_channel::{unbounded, RecvTimeoutError};
+use rust_do_it::network::{Shell, ShellCommand};
+
+/// How often the events of the network are printed while waiting for a command
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Reads commands from the standard input until `quit` or the end of the input,
+/// printing the events of the network as they arrive
+pub fn run(shell: &mut Shell) {
+    // stdin is read in its own thread, so that the events are printed
+    // while the user is typing
+    let (line_send, line_recv) = unbounded();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line_send.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    println!("type help for the list of commands");
+    prompt();
+    loop {
+        let line = match line_recv.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => {
+                print_lines(shell.poll());
+                continue;
+            },
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if line.trim().is_empty() {
+            prompt();
+            continue;
+        }
+
+        match line.parse::<ShellCommand>() {
+            Ok(ShellCommand::Quit) => break,
+            Ok(command) => match shell.execute(command) {
+                Ok(output) => println!("{}", output),
+                Err(err) => println!("error: {}", err),
+            },
+            Err(err) => println!("error: {}", err),
+        }
+        prompt();
+    }
+}
+
+fn print_lines(lines: Vec<String>) {
+    if lines.is_empty() {
+        return;
+    }
+    println!();
+    for line in lines {
+        println!("{}", line);
+    }
+    prompt();
+}
+
+fn prompt() {
+    print!("> ");
+    io::stdout().flush().ok();
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, FloodResponse, Fragment, Nack, NackType, NodeType, Packet};
+
+/// How long a check waits for a packet or an event before failing
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Why a drone failed a conformance check
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConformanceError {
+    /// Nothing was received on the channel of the node within the timeout
+    Timeout { waiting_at: NodeId },
+    /// A packet different from the expected one was received
+    UnexpectedPacket { expected: Box<Packet>, got: Box<Packet> },
+    /// The controller did not receive the expected event
+    MissingEvent(Box<DroneEvent>),
+}
+
+impl fmt::Display for ConformanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConformanceError::Timeout { waiting_at } => {
+                write!(f, "node {} did not receive any packet", waiting_at)
+            },
+            ConformanceError::UnexpectedPacket { expected, got } => {
+                write!(f, "expected {:?}, got {:?}", expected, got)
+            },
+            ConformanceError::MissingEvent(event) => {
+                write!(f, "controller did not receive {:?}", event)
+            },
+        }
+    }
+}
+
+pub type ConformanceResult = Result<(), ConformanceError>;
+
+/// A named conformance check, instantiated for a drone implementation
+pub type ConformanceCheck = (&'static str, fn() -> ConformanceResult);
+
+/// The outcome of every conformance check for a drone implementation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConformanceReport {
+    pub results: Vec<(&'static str, ConformanceResult)>,
+}
+
+impl ConformanceReport {
+    /// Returns true if the drone passed every check
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+
+    /// Returns the names of the failed checks
+    pub fn failures(&self) -> Vec<&'static str> {
+        self.results
+            .iter()
+            .filter(|(_, result)| result.is_err())
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, result) in self.results.iter() {
+            match result {
+                Ok(()) => writeln!(f, "{:<28} ok", name)?,
+                Err(err) => writeln!(f, "{:<28} FAILED: {}", name, err)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the conformance battery for the drone implementation `D`
+pub fn checks<D: Drone + 'static>() -> Vec<ConformanceCheck> {
+    vec![
+        ("fragment_forward", fragment_forward::<D>),
+        ("fragment_drop", fragment_drop::<D>),
+        ("chain_fragment_forward", chain_fragment_forward::<D>),
+        ("chain_fragment_drop", chain_fragment_drop::<D>),
+        ("flood_response_isolation", flood_response_isolation::<D>),
+        ("unexpected_recipient", unexpected_recipient::<D>),
+        ("destination_is_drone", destination_is_drone::<D>),
+        ("error_in_routing", error_in_routing::<D>),
+    ]
+}
+
+/// Runs the whole conformance battery against the drone implementation `D`
+pub fn run_all<D: Drone + 'static>() -> ConformanceReport {
+    ConformanceReport {
+        results: checks::<D>()
+            .into_iter()
+            .map(|(name, check)| (name, check()))
+            .collect(),
+    }
+}
+
+/// A drone forwards a fragment to the next hop and notifies the controller
+pub fn fragment_forward<D: Drone + 'static>() -> ConformanceResult {
+    let mut network = TestNetwork::new(&[1, 12]);
+    network.spawn::<D>(11, &[1, 12], 0.0);
+
+    let msg = fragment(vec![1, 11, 12, 21]);
+    network.send(11, msg.clone());
+
+    let mut expected = msg;
+    expected.routing_header.hop_index += 1;
+    network.expect_packet(12, &expected)?;
+    network.expect_event(&DroneEvent::PacketSent(expected))
+}
+
+/// A drone with 100% PDR drops a fragment, notifies the controller and sends a nack back
+pub fn fragment_drop<D: Drone + 'static>() -> ConformanceResult {
+    let mut network = TestNetwork::new(&[1, 12]);
+    network.spawn::<D>(11, &[1, 12], 1.0);
+
+    let msg = fragment(vec![1, 11, 12, 21]);
+    network.send(11, msg.clone());
+
+    let nack = nack(NackType::Dropped, vec![11, 1], 1);
+    network.expect_packet(1, &nack)?;
+    network.expect_event(&DroneEvent::PacketDropped(msg))?;
+    network.expect_event(&DroneEvent::PacketSent(nack))
+}
+
+/// A fragment crosses a chain of two drones and reaches the server
+pub fn chain_fragment_forward<D: Drone + 'static>() -> ConformanceResult {
+    let mut network = TestNetwork::new(&[1, 21]);
+    network.spawn::<D>(11, &[1, 12], 0.0);
+    network.spawn::<D>(12, &[11, 21], 0.0);
+
+    let msg = fragment(vec![1, 11, 12, 21]);
+    network.send(11, msg.clone());
+
+    let mut expected = msg;
+    expected.routing_header.hop_index += 2;
+    network.expect_packet(21, &expected)
+}
+
+/// The second drone of a chain drops the fragment and the nack goes back through the first one,
+/// both drones notify the controller
+pub fn chain_fragment_drop<D: Drone + 'static>() -> ConformanceResult {
+    let mut network = TestNetwork::new(&[1, 21]);
+    network.spawn::<D>(11, &[1, 12], 0.0);
+    network.spawn::<D>(12, &[11, 21], 1.0);
+
+    let mut msg = fragment(vec![1, 11, 12, 21]);
+    network.send(11, msg.clone());
+
+    let mut nack = nack(NackType::Dropped, vec![12, 11, 1], 2);
+    network.expect_packet(1, &nack)?;
+    msg.routing_header.hop_index += 1;
+    network.expect_event(&DroneEvent::PacketSent(msg.clone()))?;
+    network.expect_event(&DroneEvent::PacketDropped(msg))?;
+    network.expect_event(&DroneEvent::PacketSent(nack.clone()))?;
+    nack.routing_header.hop_index -= 1;
+    network.expect_event(&DroneEvent::PacketSent(nack))
+}
+
+/// A drone whose only other neighbour has no neighbour left answers with a flood response
+pub fn flood_response_isolation<D: Drone + 'static>() -> ConformanceResult {
+    let mut network = TestNetwork::new(&[1]);
+    network.spawn::<D>(11, &[1, 12], 0.0);
+    network.spawn::<D>(12, &[11], 0.0);
+
+    let flood_request = Packet::new_flood_request(
+        SourceRoutingHeader::new(vec![], 0),
+        0,
+        FloodRequest {
+            flood_id: 0,
+            initiator_id: 1,
+            path_trace: vec![(1, NodeType::Client)],
+        },
+    );
+    network.send(11, flood_request);
+
+    let expected = Packet::new_flood_response(
+        SourceRoutingHeader::new(vec![12, 11, 1], 2),
+        0,
+        FloodResponse {
+            flood_id: 0,
+            path_trace: vec![
+                (1, NodeType::Client),
+                (11, NodeType::Drone),
+                (12, NodeType::Drone),
+            ],
+        },
+    );
+    network.expect_packet(1, &expected)
+}
+
+/// A drone receiving a packet meant for another node sends back a nack
+pub fn unexpected_recipient<D: Drone + 'static>() -> ConformanceResult {
+    let mut network = TestNetwork::new(&[1, 21]);
+    network.spawn::<D>(11, &[1, 12], 0.0);
+    network.spawn::<D>(12, &[11, 21], 0.0);
+
+    network.send(11, fragment(vec![1, 12, 11]));
+
+    network.expect_packet(1, &nack(NackType::UnexpectedRecipient(11), vec![11, 1], 1))
+}
+
+/// A drone at the end of the route sends back a nack
+pub fn destination_is_drone<D: Drone + 'static>() -> ConformanceResult {
+    let mut network = TestNetwork::new(&[1, 21]);
+    network.spawn::<D>(11, &[1, 12], 0.0);
+    network.spawn::<D>(12, &[11, 21], 0.0);
+
+    network.send(11, fragment(vec![1, 11, 12]));
+
+    network.expect_packet(1, &nack(NackType::DestinationIsDrone, vec![12, 11, 1], 2))
+}
+
+/// A drone whose next hop is not a neighbour sends back a nack
+pub fn error_in_routing<D: Drone + 'static>() -> ConformanceResult {
+    let mut network = TestNetwork::new(&[1, 21]);
+    network.spawn::<D>(11, &[1, 12], 0.0);
+    network.spawn::<D>(12, &[11, 21], 0.0);
+
+    network.send(11, fragment(vec![1, 11, 13]));
+
+    network.expect_packet(1, &nack(NackType::ErrorInRouting(13), vec![11, 1], 1))
+}
+
+fn fragment(hops: Vec<NodeId>) -> Packet {
+    Packet::new_fragment(
+        SourceRoutingHeader::new(hops, 1),
+        1,
+        Fragment {
+            fragment_index: 1,
+            total_n_fragments: 1,
+            length: 128,
+            data: [1; 128],
+        },
+    )
+}
+
+fn nack(nack_type: NackType, hops: Vec<NodeId>, hop_index: usize) -> Packet {
+    Packet::new_nack(
+        SourceRoutingHeader::new(hops, hop_index),
+        1,
+        Nack {
+            fragment_index: 1,
+            nack_type,
+        },
+    )
+}
+
+/// The channels of a small network, the drones run in their own thread
+/// and are crashed when the network is dropped
+struct TestNetwork {
+    channels: HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)>,
+    commands: Vec<Sender<DroneCommand>>,
+    event_send: Sender<DroneEvent>,
+    event_recv: Receiver<DroneEvent>,
+    events: Vec<DroneEvent>,        // Events received while waiting for another one
+}
+
+impl TestNetwork {
+    /// Creates the channels of the clients and servers of the network
+    fn new(endpoints: &[NodeId]) -> Self {
+        let (event_send, event_recv) = unbounded();
+        Self {
+            channels: endpoints.iter().map(|id| (*id, unbounded())).collect(),
+            commands: Vec::new(),
+            event_send,
+            event_recv,
+            events: Vec::new(),
+        }
+    }
+
+    fn channel(&mut self, id: NodeId) -> &(Sender<Packet>, Receiver<Packet>) {
+        self.channels.entry(id).or_insert_with(unbounded)
+    }
+
+    fn spawn<D: Drone + 'static>(&mut self, id: NodeId, neighbours: &[NodeId], pdr: f32) {
+        let packet_recv = self.channel(id).1.clone();
+        let packet_send = neighbours
+            .iter()
+            .map(|neighbour| (*neighbour, self.channel(*neighbour).0.clone()))
+            .collect::<HashMap<_, _>>();
+        let (command_send, command_recv) = unbounded();
+        let event_send = self.event_send.clone();
+
+        thread::spawn(move || {
+            let mut drone = D::new(id, event_send, command_recv, packet_recv, packet_send, pdr);
+            drone.run();
+        });
+        self.commands.push(command_send);
+    }
+
+    fn send(&mut self, to: NodeId, packet: Packet) {
+        self.channel(to).0.send(packet).ok();
+    }
+
+    fn expect_packet(&mut self, at: NodeId, expected: &Packet) -> ConformanceResult {
+        match self.channel(at).1.recv_timeout(TIMEOUT) {
+            Ok(got) if got == *expected => Ok(()),
+            Ok(got) => Err(ConformanceError::UnexpectedPacket {
+                expected: Box::new(expected.clone()),
+                got: Box::new(got),
+            }),
+            Err(_) => Err(ConformanceError::Timeout { waiting_at: at }),
+        }
+    }
+
+    fn expect_event(&mut self, expected: &DroneEvent) -> ConformanceResult {
+        // the order of the events is not part of the protocol, keep the other ones
+        // for the next expectations
+        if let Some(position) = self.events.iter().position(|event| event == expected) {
+            self.events.remove(position);
+            return Ok(());
+        }
+        while let Ok(event) = self.event_recv.recv_timeout(TIMEOUT) {
+            if event == *expected {
+                return Ok(());
+            }
+            self.events.push(event);
+        }
+        Err(ConformanceError::MissingEvent(Box::new(expected.clone())))
+    }
+}
+
+impl Drop for TestNetwork {
+    fn drop(&mut self) {
+        for command_send in self.commands.iter() {
+            command_send.send(DroneCommand::Crash).ok();
+        }
+    }
+}
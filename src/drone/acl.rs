@@ -0,0 +1,56 @@
+use std::collections::BTreeSet;
+use wg_2024::network::NodeId;
+
+/// Which nodes a rule of an `Acl` matches
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AclRule {
+    /// Every node
+    #[default]
+    Any,
+    /// Only the listed nodes
+    Allow(BTreeSet<NodeId>),
+    /// Every node except the listed ones
+    Deny(BTreeSet<NodeId>),
+}
+
+impl AclRule {
+    /// Returns true if the rule lets the node through
+    pub fn allows(&self, id: NodeId) -> bool {
+        match self {
+            AclRule::Any => true,
+            AclRule::Allow(ids) => ids.contains(&id),
+            AclRule::Deny(ids) => !ids.contains(&id),
+        }
+    }
+}
+
+/// What a drone does with a fragment denied by its `Acl`. The acks, nacks and
+/// flood responses cannot be lost, whatever the response they are sent to the
+/// controller with `DroneEvent::ControllerShortcut`, along with a `DroneReport::PacketDenied`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AclResponse {
+    /// Send back an `ErrorInRouting` nack naming the drone
+    #[default]
+    Nack,
+    /// Discard the fragment and send a `DroneReport::PacketDenied` to the controller
+    Report,
+}
+
+/// The sources and destinations a drone relays packets for.
+///
+/// The source of a packet is the first hop of its route and the destination the
+/// last one, so the acks and nacks going back to a denied client are denied too,
+/// and left to the controller, see `AclResponse`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Acl {
+    pub sources: AclRule,
+    pub destinations: AclRule,
+    pub response: AclResponse,
+}
+
+impl Acl {
+    /// Returns true if the drone can relay packets from `source` to `destination`
+    pub fn allows(&self, source: NodeId, destination: NodeId) -> bool {
+        self.sources.allows(source) && self.destinations.allows(destination)
+    }
+}
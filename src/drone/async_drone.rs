@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use async_channel::{Receiver, RecvError, Sender};
+use futures_lite::future;
+use log::{debug, error, info, warn};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+use super::acl::Acl;
+#[cfg(feature = "byzantine")]
+use super::byzantine::ByzantineMode;
+use super::command::RustDoItCommand;
+use super::core::{Action, DroneCore};
+use super::pdr_schedule::PdrSchedule;
+use super::policy::{LoopPolicy, UnreachablePolicy};
+use super::rate_limit::FloodRateLimit;
+use super::report::DroneReport;
+use super::stats::DroneStats;
+use super::topology::Topology;
+
+/// A `RustDoIt` drone that runs as a task on an async executor instead of
+/// in its own thread.
+///
+/// The decisions are taken by the same `DroneCore` of the blocking drone,
+/// this type only awaits on the async channels and executes the resulting actions.
+/// Neighbours reachable through an async channel are added with `add_sender`,
+/// `DroneCommand::AddSender` keeps working for neighbours using crossbeam channels.
+#[derive(Debug)]
+pub struct AsyncRustDoIt {
+    controller_send: Sender<DroneEvent>,
+    controller_recv: Receiver<DroneCommand>,
+    packet_recv: Receiver<Packet>,
+    packet_send: HashMap<NodeId, Link>,
+    core: DroneCore,
+    report_send: Option<Sender<DroneReport>>,
+    created: Instant,
+}
+
+#[derive(Debug)]
+enum Link {
+    Async(Sender<Packet>),
+    Blocking(crossbeam_channel::Sender<Packet>),
+}
+
+enum Input {
+    Command(Result<DroneCommand, RecvError>),
+    Packet(Result<Packet, RecvError>),
+}
+
+impl AsyncRustDoIt {
+
+    pub fn new(
+        id: NodeId,
+        controller_send: Sender<DroneEvent>,
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+        packet_send: HashMap<NodeId, Sender<Packet>>,
+        pdr: f32,
+    ) -> Self {
+        Self {
+            controller_send,
+            controller_recv,
+            packet_recv,
+            core: DroneCore::new(id, packet_send.keys().copied(), pdr),
+            packet_send: packet_send
+                .into_iter()
+                .map(|(node_id, sender)| (node_id, Link::Async(sender)))
+                .collect(),
+            report_send: None,
+            created: Instant::now(),
+        }
+    }
+
+    /// Reseeds the random number generator used to decide packet drops
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.core = self.core.with_seed(seed);
+        self
+    }
+
+    /// Sets what the drone does with routes going through the same node twice
+    pub fn with_loop_policy(mut self, loop_policy: LoopPolicy) -> Self {
+        self.core = self.core.with_loop_policy(loop_policy);
+        self
+    }
+
+    /// Sets what the drone does with packets whose next hop is not a neighbour
+    pub fn with_unreachable_policy(mut self, unreachable_policy: UnreachablePolicy) -> Self {
+        self.core = self.core.with_unreachable_policy(unreachable_policy);
+        self
+    }
+
+    /// Enables or disables the local repair of the routes around missing neighbours
+    pub fn with_local_repair(mut self, local_repair: bool) -> Self {
+        self.core = self.core.with_local_repair(local_repair);
+        self
+    }
+
+    /// Sets the channel on which the drone sends its `DroneReport`s
+    pub fn with_reports(mut self, report_send: Sender<DroneReport>) -> Self {
+        self.report_send = Some(report_send);
+        self
+    }
+
+    /// Sets the maximum length of the path trace of the flood requests,
+    /// see `DroneCore::with_max_flood_path`
+    pub fn with_max_flood_path(mut self, max_flood_path: usize) -> Self {
+        self.core = self.core.with_max_flood_path(max_flood_path);
+        self
+    }
+
+    /// Limits the flood requests forwarded for each initiator,
+    /// see `DroneCore::with_flood_rate_limit`
+    pub fn with_flood_rate_limit(mut self, flood_rate_limit: FloodRateLimit) -> Self {
+        self.core = self.core.with_flood_rate_limit(flood_rate_limit);
+        self
+    }
+
+    /// Sets the sources and destinations the drone relays packets for
+    pub fn with_acl(mut self, acl: Acl) -> Self {
+        self.core = self.core.with_acl(acl);
+        self
+    }
+
+    /// Handles a command that is not part of the protocol
+    pub async fn handle_extended_command(&mut self, command: RustDoItCommand) {
+        let actions = self.core.handle_extended_command(&command);
+        self.execute(actions).await;
+    }
+
+    /// Makes the packet drop rate follow a schedule evaluated against the logical clock,
+    /// until the next `DroneCommand::SetPacketDropRate`
+    pub fn with_pdr_schedule(mut self, pdr_schedule: PdrSchedule) -> Self {
+        self.core = self.core.with_pdr_schedule(pdr_schedule);
+        self
+    }
+
+    /// Makes the drone misbehave in the given way, see `ByzantineMode`.
+    /// Only meant for adversarial testing
+    #[cfg(feature = "byzantine")]
+    pub fn with_byzantine_mode(mut self, mode: ByzantineMode) -> Self {
+        self.core = self.core.with_byzantine_mode(mode);
+        self
+    }
+
+    /// Makes the drone flip bits of the fragments it forwards,
+    /// see `DroneCore::with_bit_error_rate`
+    pub fn with_bit_error_rate(mut self, bit_error_rate: f64) -> Self {
+        self.core = self.core.with_bit_error_rate(bit_error_rate);
+        self
+    }
+
+    /// Sets the logical time of the drone, in the unit of the flood rate limit.
+    /// `run` sets it to the milliseconds elapsed since the drone was created
+    pub fn set_clock(&mut self, now: u64) {
+        self.core.set_clock(now);
+    }
+
+    /// Enables or disables the learning of the topology from the relayed flood traffic
+    pub fn with_topology_learning(mut self, learning: bool) -> Self {
+        self.core = self.core.with_topology_learning(learning);
+        self
+    }
+
+    /// Returns the topology learned so far, None if the learning is off
+    pub fn topology(&self) -> Option<&Topology> {
+        self.core.topology()
+    }
+
+    /// Sends the learned topology on the report channel, if it changed since the
+    /// last report. There is no timer in the async drone, the owner calls this
+    /// from the timer of its executor
+    pub async fn report_topology(&mut self) {
+        let actions = self.core.topology_report();
+        self.execute(actions).await;
+    }
+
+    /// Makes the drone update the given counters instead of its own
+    pub fn with_stats(mut self, stats: Arc<DroneStats>) -> Self {
+        self.core = self.core.with_stats(stats);
+        self
+    }
+
+    /// Returns the counters of the drone
+    pub fn stats(&self) -> Arc<DroneStats> {
+        self.core.stats()
+    }
+
+    /// Adds a neighbour reachable through an async channel
+    pub fn add_sender(&mut self, node_id: NodeId, sender: Sender<Packet>) {
+        self.core.add_neighbour(node_id);
+        self.packet_send.insert(node_id, Link::Async(sender));
+    }
+
+    pub async fn run(&mut self) {
+        let mut commands_closed = false;
+        let mut packets_closed = false;
+
+        while !(commands_closed && packets_closed) {
+            // `or` polls the first future before the second one, which gives
+            // the same priority to the commands as `select_biased!` in `RustDoIt::run`
+            let input = if commands_closed {
+                Input::Packet(self.packet_recv.recv().await)
+            } else if packets_closed {
+                Input::Command(self.controller_recv.recv().await)
+            } else {
+                future::or(
+                    async { Input::Command(self.controller_recv.recv().await) },
+                    async { Input::Packet(self.packet_recv.recv().await) },
+                ).await
+            };
+            self.core.set_clock(self.created.elapsed().as_millis() as u64);
+
+            match input {
+                Input::Command(Ok(command)) => {
+                    info!("Drone {} received command {:?}", self.core.id(), command);
+                    if matches!(command, DroneCommand::Crash) {
+                        self.handle_command(command).await;
+                        return;
+                    }
+                    self.handle_command(command).await;
+                },
+                Input::Packet(Ok(packet)) => {
+                    info!("Drone {} received packet {:?}", self.core.id(), packet);
+                    let actions = self.core.handle_packet(packet);
+                    self.execute(actions).await;
+                },
+                Input::Command(Err(_)) => commands_closed = true,
+                Input::Packet(Err(_)) => packets_closed = true,
+            }
+        }
+    }
+
+    async fn handle_command(&mut self, command: DroneCommand) {
+        let actions = self.core.handle_command(&command);
+        self.execute(actions).await;
+
+        match command {
+            DroneCommand::AddSender(node_id, sender) => {
+                self.packet_send.insert(node_id, Link::Blocking(sender));
+            },
+
+            DroneCommand::RemoveSender(node_id) => {
+                self.packet_send.remove(&node_id);
+            },
+
+            DroneCommand::Crash => {
+                while let Ok(packet) = self.packet_recv.try_recv() {
+                    let actions = self.core.handle_packet_crash(packet);
+                    self.execute(actions).await;
+                }
+            },
+
+            DroneCommand::SetPacketDropRate(_) => {},
+        }
+    }
+
+    async fn execute(&self, actions: Vec<Action>) {
+        // This function executes the actions decided by the core, in order,
+        // reporting the failed sends exactly as `RustDoIt` does
+
+        for action in actions {
+            match action {
+                Action::Forward(next_hop, packet) => {
+                    let sent = match self.packet_send.get(&next_hop) {
+                        Some(Link::Async(sender)) => sender.send(packet.clone()).await.is_ok(),
+                        Some(Link::Blocking(sender)) => sender.send(packet.clone()).is_ok(),
+                        None => {
+                            warn!("Drone {} has no sender for {}", self.core.id(), next_hop);
+                            false
+                        }
+                    };
+
+                    let event = match (sent, &packet.pack_type) {
+                        (true, _) => DroneEvent::PacketSent(packet),
+                        (false, PacketType::MsgFragment(_)) => DroneEvent::PacketDropped(packet),
+                        (false, _) => DroneEvent::ControllerShortcut(packet),
+                    };
+                    self.send_event(event).await;
+                },
+                Action::Event(event) => self.send_event(event).await,
+                Action::Drop(packet) => {
+                    debug!("Drone {} discarded packet {:?}", self.core.id(), packet);
+                },
+                Action::Report(report) => match &self.report_send {
+                    Some(report_send) => if report_send.send(report).await.is_err() {
+                        error!("Drone {} could not send report to controller", self.core.id());
+                    },
+                    None => report.log_unsent(),
+                },
+            }
+        }
+    }
+
+    async fn send_event(&self, event: DroneEvent) {
+        self.core.counters().add_event(&event);
+        if self.controller_send.send(event).await.is_err() {
+            error!("Drone {} could not send packet to controller", self.core.id());
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+use std::num::NonZeroUsize;
+use log::{debug, warn};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Nack, NackType, NodeType, Packet, PacketType};
+use super::core::Action;
+
+/// A misbehaviour of a drone, to test how clients and servers cope with an
+/// adversarial network. Only available with the `byzantine` feature, never
+/// enable it outside of tests.
+///
+/// Every mode changes a single aspect of the drone, which otherwise follows the protocol
+#[derive(Debug, Clone, PartialEq)]
+pub enum ByzantineMode {
+    /// Accepts the fragments routed through the drone but never forwards them,
+    /// without any nack or event
+    BlackHole,
+    /// Like `BlackHole`, only for the fragments of the listed sessions
+    GreyHole(BTreeSet<u64>),
+    /// Replaces the hops after the drone in the route of every routed packet
+    RouteTamperer(Vec<NodeId>),
+    /// Forwards the fragments, but also sends back to the source a nack of this
+    /// type for each of them
+    FakeNack(NackType),
+    /// Sends every flood request this many times to each neighbour
+    FloodAmplifier(NonZeroUsize),
+    /// Inserts these entries just before the drone in the path trace of the
+    /// flood requests and flood responses it sends
+    PathTraceForger(Vec<(NodeId, NodeType)>),
+}
+
+impl ByzantineMode {
+    /// Applies the mode to a packet received by the drone, before it is handled.
+    /// Returns the packet to be handled normally, or the actions replacing it
+    pub(crate) fn intercept(&self, id: NodeId, mut packet: Packet) -> Result<Packet, Vec<Action>> {
+        if packet.routing_header.current_hop() != Some(id) {
+            return Ok(packet);
+        }
+
+        match (self, &packet.pack_type) {
+            (ByzantineMode::BlackHole, PacketType::MsgFragment(_)) => {
+                debug!("Drone {} swallowing fragment of session {}", id, packet.session_id);
+                Err(vec![Action::Drop(packet)])
+            },
+            (ByzantineMode::GreyHole(sessions), PacketType::MsgFragment(_)) if sessions.contains(&packet.session_id) => {
+                debug!("Drone {} swallowing fragment of session {}", id, packet.session_id);
+                Err(vec![Action::Drop(packet)])
+            },
+            (ByzantineMode::RouteTamperer(hops), _) => {
+                let srh = &mut packet.routing_header;
+                srh.hops.truncate(srh.hop_index + 1);
+                srh.hops.extend_from_slice(hops);
+                warn!("Drone {} rewrote route to {:?}", id, srh.hops);
+                Ok(packet)
+            },
+            _ => Ok(packet),
+        }
+    }
+
+    /// Applies the mode to the actions decided for a packet
+    pub(crate) fn rewrite(&self, id: NodeId, actions: Vec<Action>) -> Vec<Action> {
+        let mut rewritten = Vec::with_capacity(actions.len());
+        for action in actions {
+            match (self, action) {
+                (ByzantineMode::FakeNack(nack_type), Action::Forward(next_hop, packet)) => {
+                    let nack = fake_nack(*nack_type, &packet);
+                    rewritten.push(Action::Forward(next_hop, packet));
+                    if let Some(nack) = nack {
+                        warn!("Drone {} injecting fake nack {:?}", id, nack_type);
+                        rewritten.push(nack);
+                    }
+                },
+                (ByzantineMode::FloodAmplifier(copies), Action::Forward(next_hop, packet))
+                    if matches!(packet.pack_type, PacketType::FloodRequest(_)) =>
+                {
+                    for _ in 1..copies.get() {
+                        rewritten.push(Action::Forward(next_hop, packet.clone()));
+                    }
+                    rewritten.push(Action::Forward(next_hop, packet));
+                },
+                (ByzantineMode::PathTraceForger(entries), Action::Forward(next_hop, mut packet)) => {
+                    let path_trace = match &mut packet.pack_type {
+                        PacketType::FloodRequest(flood_request) => Some(&mut flood_request.path_trace),
+                        PacketType::FloodResponse(flood_response) => Some(&mut flood_response.path_trace),
+                        _ => None,
+                    };
+                    if let Some(path_trace) = path_trace {
+                        if let Some(position) = path_trace.iter().position(|(node, _)| *node == id) {
+                            path_trace.splice(position..position, entries.iter().copied());
+                            warn!("Drone {} forged path trace {:?}", id, path_trace);
+                        }
+                    }
+                    rewritten.push(Action::Forward(next_hop, packet));
+                },
+                (_, action) => rewritten.push(action),
+            }
+        }
+        rewritten
+    }
+}
+
+fn fake_nack(nack_type: NackType, packet: &Packet) -> Option<Action> {
+    // This function builds a nack going back to the source of a fragment
+    // the drone is forwarding, along the hops the fragment travelled
+    // ### Parameters:
+    // - `nack_type`: The type of the nack
+    // - `packet`: The fragment, with the hop index already increased
+    //
+    // ### Returns:
+    // - `Option<Action>`: The forward of the nack, None if the packet is not a fragment
+
+    let PacketType::MsgFragment(fragment) = &packet.pack_type else {
+        return None;
+    };
+    let srh = &packet.routing_header;
+    let mut hops = srh.hops.get(..srh.hop_index)?.to_vec();
+    hops.reverse();
+    let previous = *hops.get(1)?;
+
+    let nack = Packet::new_nack(
+        SourceRoutingHeader::new(hops, 1),
+        packet.session_id,
+        Nack {
+            fragment_index: fragment.fragment_index,
+            nack_type,
+        },
+    );
+    Some(Action::Forward(previous, nack))
+}
@@ -0,0 +1,13 @@
+use super::acl::Acl;
+use super::pdr_schedule::PdrSchedule;
+
+/// The commands a `RustDoIt` drone accepts beyond the `DroneCommand`s of the
+/// protocol, received on the channel set with `RustDoIt::with_extended_commands`
+#[derive(Debug, Clone, PartialEq)]
+pub enum RustDoItCommand {
+    /// Replaces the access control list of the drone
+    SetAcl(Acl),
+    /// Makes the packet drop rate follow a schedule, until the next
+    /// `DroneCommand::SetPacketDropRate`
+    SetPdrSchedule(PdrSchedule),
+}
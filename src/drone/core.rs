@@ -0,0 +1,1086 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use log::{debug, error, info, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, Nack, NackType, Packet, PacketType, NodeType, FloodRequest, FloodResponse};
+use super::acl::{Acl, AclResponse};
+#[cfg(feature = "byzantine")]
+use super::byzantine::ByzantineMode;
+use super::command::RustDoItCommand;
+use super::header::{validate_header, MalformedHeader};
+use super::pdr_schedule::PdrSchedule;
+use super::policy::{LoopPolicy, UnreachableAction, UnreachablePolicy};
+use super::rate_limit::{FloodRateLimit, TokenBucket};
+use super::report::DroneReport;
+use super::stats::DroneStats;
+use super::topology::Topology;
+
+/// What the drone decided to do with a packet or a command.
+/// The actions must be executed in order by the adapter owning the channels
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Send the packet to the neighbour. The adapter reports `DroneEvent::PacketSent`
+    /// if the send succeeds, `DroneEvent::PacketDropped` (fragments) or
+    /// `DroneEvent::ControllerShortcut` (any other packet) if it fails
+    Forward(NodeId, Packet),
+    /// Send the event to the controller
+    Event(DroneEvent),
+    /// Discard the packet without notifying anyone
+    Drop(Packet),
+    /// Send the report on the report channel of the drone, if it has one
+    Report(DroneReport),
+}
+
+/// The decision logic of a `RustDoIt` drone, free of any channel.
+///
+/// The core only knows the ids of the neighbours, it takes a `Packet` or a
+/// `DroneCommand` and returns the list of `Action`s to be executed
+#[derive(Debug)]
+pub struct DroneCore {
+    id: NodeId,
+    neighbours: HashSet<NodeId>,
+    flood_session: HashSet<(u64, NodeId)>,
+    pdr: f32,
+    pdr_schedule: Option<PdrSchedule>,                  // Packet drop rate following the clock, replacing `pdr` when set
+    bit_error_rate: f64,                                // Probability of flipping each bit of the payload of a forwarded fragment
+    rng: StdRng,                                        // Source of randomness for the packet drop decision
+    loop_policy: LoopPolicy,                            // What to do with routes going through the same node twice
+    unreachable_policy: UnreachablePolicy,              // What to do with packets whose next hop is not a neighbour
+    routes: HashMap<NodeId, Vec<NodeId>>,               // Shortest known route to each node, starting from a neighbour
+    local_repair: bool,                                 // Whether routes are repaired around missing neighbours
+    topology: Option<Topology>,                         // Links learned from the relayed flood traffic, if learning is on
+    topology_changed: bool,                             // Whether the topology changed since the last report
+    max_flood_path: Option<usize>,                      // Length of the path trace after which floods are answered
+    flood_rate_limit: Option<FloodRateLimit>,           // Flood requests forwarded for each initiator
+    flood_buckets: HashMap<NodeId, TokenBucket>,        // Token bucket of each initiator
+    clock: u64,                                         // Logical time, set by the owner of the core
+    acl: Acl,                                           // Sources and destinations the drone relays packets for
+    stats: Arc<DroneStats>,                             // Counters shared with the owner of the drone
+    #[cfg(feature = "byzantine")]
+    byzantine: Option<ByzantineMode>,                   // Misbehaviour of the drone, for adversarial testing only
+}
+
+impl DroneCore {
+
+    pub fn new(id: NodeId, neighbours: impl IntoIterator<Item = NodeId>, pdr: f32) -> Self {
+        Self {
+            id,
+            neighbours: neighbours.into_iter().collect(),
+            flood_session: HashSet::new(),
+            pdr,
+            pdr_schedule: None,
+            bit_error_rate: 0.0,
+            rng: StdRng::from_entropy(),
+            loop_policy: LoopPolicy::default(),
+            unreachable_policy: UnreachablePolicy::default(),
+            routes: HashMap::new(),
+            local_repair: false,
+            topology: None,
+            topology_changed: false,
+            max_flood_path: None,
+            flood_rate_limit: None,
+            flood_buckets: HashMap::new(),
+            clock: 0,
+            acl: Acl::default(),
+            stats: Arc::new(DroneStats::default()),
+            #[cfg(feature = "byzantine")]
+            byzantine: None,
+        }
+    }
+
+    /// Reseeds the random number generator used to decide packet drops,
+    /// making the behaviour of the drone reproducible across runs
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Sets what the drone does with routes going through the same node twice
+    pub fn with_loop_policy(mut self, loop_policy: LoopPolicy) -> Self {
+        self.loop_policy = loop_policy;
+        self
+    }
+
+    /// Sets what the drone does with packets whose next hop is not a neighbour
+    pub fn with_unreachable_policy(mut self, unreachable_policy: UnreachablePolicy) -> Self {
+        self.unreachable_policy = unreachable_policy;
+        self
+    }
+
+    /// Enables or disables the local repair of the routes: when the next hop of a
+    /// packet is not a neighbour, the drone looks for a neighbour linked to the hop
+    /// after it in the links learned from the relayed flood traffic, and splices
+    /// it in the route. Enabling it turns on the learning of the links
+    pub fn with_local_repair(mut self, local_repair: bool) -> Self {
+        self.local_repair = local_repair;
+        if local_repair && self.topology.is_none() {
+            self.topology = Some(Topology::default());
+        }
+        self
+    }
+
+    /// Sets the maximum length of the path trace of a flood request: a drone whose
+    /// own entry makes the path trace `max_flood_path` long answers with a flood
+    /// response instead of forwarding the request
+    pub fn with_max_flood_path(mut self, max_flood_path: usize) -> Self {
+        self.max_flood_path = Some(max_flood_path);
+        self
+    }
+
+    /// Limits the flood requests forwarded for each initiator. The requests over
+    /// the limit are answered with a flood response and reported to the controller
+    pub fn with_flood_rate_limit(mut self, flood_rate_limit: FloodRateLimit) -> Self {
+        self.flood_rate_limit = Some(flood_rate_limit);
+        self
+    }
+
+    /// Sets the sources and destinations the drone relays packets for
+    pub fn with_acl(mut self, acl: Acl) -> Self {
+        self.acl = acl;
+        self
+    }
+
+    /// Returns the access control list of the drone
+    pub fn acl(&self) -> &Acl {
+        &self.acl
+    }
+
+    /// Sets the logical time of the drone, used by the time based limits.
+    /// The time must not go backwards
+    pub fn set_clock(&mut self, now: u64) {
+        self.clock = self.clock.max(now);
+    }
+
+    /// Enables or disables the learning of the topology from the path traces of
+    /// the relayed flood requests and flood responses. The learning stays on
+    /// while local repair is enabled, since the repair needs the learned links
+    pub fn with_topology_learning(mut self, learning: bool) -> Self {
+        if learning && self.topology.is_none() {
+            self.topology = Some(Topology::default());
+        } else if !learning && !self.local_repair {
+            self.topology = None;
+        }
+        self
+    }
+
+    /// Returns the topology learned so far, None if the learning is off
+    pub fn topology(&self) -> Option<&Topology> {
+        self.topology.as_ref()
+    }
+
+    /// Returns a `DroneReport::Topology` action if the topology learned
+    /// changed since the last call
+    pub fn topology_report(&mut self) -> Vec<Action> {
+        match &self.topology {
+            Some(topology) if self.topology_changed => {
+                self.topology_changed = false;
+                vec![Action::Report(DroneReport::Topology {
+                    drone: self.id,
+                    topology: topology.clone(),
+                })]
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Makes the drone misbehave in the given way, see `ByzantineMode`.
+    /// Only meant for adversarial testing
+    #[cfg(feature = "byzantine")]
+    pub fn with_byzantine_mode(mut self, mode: ByzantineMode) -> Self {
+        self.byzantine = Some(mode);
+        self
+    }
+
+    /// Returns the misbehaviour of the drone, if any
+    #[cfg(feature = "byzantine")]
+    pub fn byzantine_mode(&self) -> Option<&ByzantineMode> {
+        self.byzantine.as_ref()
+    }
+
+    /// Returns the id of the drone
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Makes the packet drop rate of the drone follow a schedule, evaluated against
+    /// the logical clock. A `DroneCommand::SetPacketDropRate` removes the schedule
+    pub fn with_pdr_schedule(mut self, pdr_schedule: PdrSchedule) -> Self {
+        self.pdr_schedule = Some(pdr_schedule);
+        self
+    }
+
+    /// Returns the schedule followed by the packet drop rate, if any
+    pub fn pdr_schedule(&self) -> Option<&PdrSchedule> {
+        self.pdr_schedule.as_ref()
+    }
+
+    /// Returns the packet drop rate of the drone at the current logical time
+    pub fn pdr(&self) -> f32 {
+        match &self.pdr_schedule {
+            Some(pdr_schedule) => pdr_schedule.pdr_at(self.clock),
+            None => self.pdr,
+        }
+    }
+
+    /// Makes the drone flip each bit of the payload of the fragments it forwards
+    /// with the given probability, clamped between 0 and 1. A rate that is not
+    /// finite disables the flips. The header of the fragment and the bytes past
+    /// its length are left untouched
+    pub fn with_bit_error_rate(mut self, bit_error_rate: f64) -> Self {
+        self.bit_error_rate = if bit_error_rate.is_finite() { bit_error_rate.clamp(0.0, 1.0) } else { 0.0 };
+        self
+    }
+
+    /// Returns the probability of flipping each bit of a forwarded fragment
+    pub fn bit_error_rate(&self) -> f64 {
+        self.bit_error_rate
+    }
+
+    /// Makes the drone update the given counters, e.g. to share them with the
+    /// owner of the drone before it is built
+    pub fn with_stats(mut self, stats: Arc<DroneStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Returns the counters of the drone, which keep being updated while it runs.
+    /// The events sent to the controller are counted by the adapter executing the actions
+    pub fn stats(&self) -> Arc<DroneStats> {
+        Arc::clone(&self.stats)
+    }
+
+    pub(crate) fn counters(&self) -> &DroneStats {
+        &self.stats
+    }
+
+    /// Returns the ids of the neighbours of the drone
+    pub fn neighbours(&self) -> &HashSet<NodeId> {
+        &self.neighbours
+    }
+
+    /// Returns the shortest route known to reach the node, made of the hops
+    /// after this drone, learned from the routes of the relayed packets
+    pub fn known_route(&self, node_id: NodeId) -> Option<&[NodeId]> {
+        self.routes.get(&node_id).map(Vec::as_slice)
+    }
+
+    /// Adds a neighbour reachable through a channel not carried by a `DroneCommand`
+    pub fn add_neighbour(&mut self, node_id: NodeId) {
+        self.neighbours.insert(node_id);
+        debug!("Drone {} added sender {}", self.id, node_id);
+    }
+
+    pub fn handle_command(&mut self, command: &DroneCommand) -> Vec<Action> {
+        // This function updates the state of the drone according to the command
+        // The senders carried by `DroneCommand::AddSender` are kept by the adapter,
+        // and the pending packets of a `DroneCommand::Crash` are drained by the
+        // adapter and handled with `handle_packet_crash`
+        // ### Parameters:
+        // - `command`: The command to be handled
+
+        match command {
+            DroneCommand::AddSender(node_id, _) => self.add_neighbour(*node_id),
+
+            DroneCommand::SetPacketDropRate(pdr) => {
+                if (0.0..=1.0).contains(pdr) {
+                    self.pdr = *pdr;
+                    self.pdr_schedule = None;
+                    debug!("Drone {} set PDR to {}", self.id, self.pdr);
+                } else {
+                    warn!(
+                        "Drone {} could not set PDR to {}, value must be between 0.0 and 1.0",
+                        self.id,
+                        pdr
+                    );
+                }
+            },
+
+            DroneCommand::Crash => {
+                debug!("Drone {} crashed", self.id);
+            },
+
+            DroneCommand::RemoveSender(node_id) => {
+                if self.neighbours.remove(node_id) {
+                    self.routes.retain(|_, route| route.first() != Some(node_id));
+                    debug!("Drone {} removed sender {}", self.id, node_id);
+                } else {
+                    warn!(
+                        "Drone {} could not remove sender {} because it is not a neighbour",
+                        self.id,
+                        node_id
+                    );
+                }
+            },
+        }
+
+        Vec::new()
+    }
+
+    pub fn handle_extended_command(&mut self, command: &RustDoItCommand) -> Vec<Action> {
+        // This function updates the state of the drone according to a command
+        // that is not part of the protocol
+        // ### Parameters:
+        // - `command`: The command to be handled
+
+        match command {
+            RustDoItCommand::SetAcl(acl) => {
+                self.acl = acl.clone();
+                debug!("Drone {} set ACL to {:?}", self.id, self.acl);
+            },
+            RustDoItCommand::SetPdrSchedule(pdr_schedule) => {
+                self.pdr_schedule = Some(pdr_schedule.clone());
+                debug!("Drone {} set PDR schedule to {:?}", self.id, pdr_schedule);
+            },
+        }
+
+        Vec::new()
+    }
+
+    pub fn handle_packet(&mut self, packet: Packet) -> Vec<Action> {
+        // This function handles the received packet
+        // It checks the packet type and calls the appropriate function
+        // to handle the packet. A byzantine drone changes the packet
+        // before and the actions after
+        // ### Parameters:
+        // - `packet`: The packet to be handled
+
+        #[cfg(feature = "byzantine")]
+        if let Some(mode) = self.byzantine.take() {
+            let actions = match mode.intercept(self.id, packet) {
+                Ok(packet) => {
+                    let actions = self.handle_packet_honest(packet);
+                    mode.rewrite(self.id, actions)
+                },
+                Err(actions) => actions,
+            };
+            self.byzantine = Some(mode);
+            return actions;
+        }
+
+        self.handle_packet_honest(packet)
+    }
+
+    fn handle_packet_honest(&mut self, packet: Packet) -> Vec<Action> {
+        let mut actions = Vec::new();
+        match packet.pack_type {
+            PacketType::FloodRequest(flood_request) => self.handle_flood_request(
+                flood_request,
+                packet.session_id,
+                &mut actions
+            ),
+
+            _ => self.route_packet(packet, &mut actions),
+
+        }
+        actions
+    }
+
+    /// This function handles the packet in case of a crash
+    pub fn handle_packet_crash(&mut self, packet: Packet) -> Vec<Action> {
+        let mut actions = Vec::new();
+        match packet.pack_type {
+            PacketType::MsgFragment(_) => {
+                // the nack must go back through this drone's previous hop, so the
+                // route of the nack includes this drone like in `check_packet`
+                let mut srh = packet.routing_header.clone();
+                if srh.current_hop().is_some() {
+                    srh.increase_hop_index();
+                }
+                self.generate_nack(
+                    NackType::ErrorInRouting(self.id),
+                    packet.get_fragment_index(),
+                    srh,
+                    packet.session_id,
+                    &mut actions
+                );
+                actions.push(Action::Event(DroneEvent::PacketDropped(packet)));
+            },
+
+            PacketType::FloodRequest(_) => {
+                // flood requests have no routing header to send a nack back with,
+                // the initiator will not get an answer from this drone
+                error!("Drone {} crashed, dropping flood request", self.id);
+                actions.push(Action::Event(DroneEvent::PacketDropped(packet)));
+            },
+
+            _ => self.route_packet(packet, &mut actions),
+
+        }
+        actions
+    }
+
+    fn route_packet(&mut self, packet: Packet, actions: &mut Vec<Action>) {
+        // This function checks that the packet is for this drone before anything
+        // else, so that a misrouted packet is refused as such whatever the rest of
+        // its header, then validates the header and handles the packet
+        // A header without a current hop past the source is left to the validation
+        // ### Parameters:
+        // - `packet`: The packet to be routed
+
+        let srh = &packet.routing_header;
+        if srh.hop_index > 0 && srh.current_hop().is_some() && !self.is_correct_recipient(
+            packet.get_fragment_index(),
+            srh,
+            packet.session_id,
+            actions
+        ) {
+            return;
+        }
+
+        if let Some(packet) = self.validate_packet(packet, actions) {
+            self.check_packet(packet, actions)
+        }
+    }
+
+    fn validate_packet(&self, mut packet: Packet, actions: &mut Vec<Action>) -> Option<Packet> {
+        // This function checks the source routing header of the packet before
+        // anything else reads it, and applies the response documented on
+        // `MalformedHeader` when the header is malformed
+        // ### Parameters:
+        // - `packet`: The packet to be validated
+        //
+        // ### Returns:
+        // - `Option<Packet>`: The packet if it can be handled normally, None otherwise
+
+        let Err(malformed) = validate_header(&packet.routing_header) else {
+            return Some(packet);
+        };
+
+        match malformed {
+            MalformedHeader::Loop(node_id) => {
+                self.stats.add_routing_loop();
+                match self.loop_policy {
+                    LoopPolicy::Forward => {
+                        debug!("Drone {} forwarding packet on a route with a loop: {}", self.id, malformed);
+                        Some(packet)
+                    },
+                    LoopPolicy::Nack => {
+                        warn!("Drone {} refusing route with a loop: {}", self.id, malformed);
+                        packet.routing_header.increase_hop_index();
+                        self.refuse_route(node_id, packet, actions);
+                        None
+                    },
+                    LoopPolicy::Shortcut => {
+                        warn!("Drone {} sending packet with a loop to controller: {}", self.id, malformed);
+                        actions.push(Action::Event(DroneEvent::ControllerShortcut(packet)));
+                        None
+                    },
+                }
+            },
+
+            MalformedHeader::DuplicateConsecutiveHops(node_id) => {
+                warn!("Drone {} received packet with malformed route: {}", self.id, malformed);
+                packet.routing_header.increase_hop_index();
+                self.refuse_route(node_id, packet, actions);
+                None
+            },
+
+            MalformedHeader::Empty => {
+                error!("Drone {} dropping packet with malformed route: {}", self.id, malformed);
+                actions.push(Action::Drop(packet));
+                None
+            },
+
+            MalformedHeader::IndexZero | MalformedHeader::IndexPastEnd => {
+                if let PacketType::MsgFragment(_) = packet.pack_type {
+                    error!("Drone {} dropping fragment with malformed route: {}", self.id, malformed);
+                    actions.push(Action::Drop(packet));
+                } else {
+                    warn!("Drone {} sending packet with malformed route to controller: {}", self.id, malformed);
+                    actions.push(Action::Event(DroneEvent::ControllerShortcut(packet)));
+                }
+                None
+            },
+        }
+    }
+
+    fn check_packet(&mut self, mut packet: Packet, actions: &mut Vec<Action>) {
+        // This function is responsible for checking the packet before forwarding it.
+        // The packet is already known to be for this drone. It checks
+        // if the next hop is in the list of neighbours
+        // if the next hop is not in the list of neighbours,
+        // it generates a nack of type NackType::ErrorInRouting
+        // else increases the hop index and forwards the packet to the next hop
+        // ### Parameters:
+        // - `packet`: The packet to be forwarded
+
+        // Step 0: check if the packet is droppable or not
+        let droppable = matches!(packet.pack_type, PacketType::MsgFragment(_));
+
+        // Step 1: the packet is for this drone, checked by `route_packet`
+        // Step 1.1: Check if the drone relays packets between the source and the destination
+        if !self.is_allowed(&packet, actions) {
+            return;
+        }
+        self.learn_routes(&packet.routing_header);
+        if let (PacketType::FloodResponse(flood_response), Some(topology)) = (&packet.pack_type, &mut self.topology) {
+            self.topology_changed |= topology.learn_path(&flood_response.path_trace);
+        }
+
+        // Step 2: Check if there is a next node
+        match packet.routing_header.next_hop() {
+            None => {
+                self.generate_nack(
+                    NackType::DestinationIsDrone,
+                    packet.get_fragment_index(),
+                    packet.routing_header,
+                    packet.session_id,
+                    actions
+                );
+
+            },
+            Some(mut next_hop) => {
+                // Step 3: increase the hop index
+                packet.routing_header.increase_hop_index();
+
+                // Step 4: Check if the next hop is in the list of neighbours
+                if !self.neighbours.contains(&next_hop) {
+                    warn!("Drone {} not found in the list of neighbours, probably crashed.", &next_hop);
+                    // step 4.1: try to repair the route around the missing hop,
+                    // or to take the alternate route if the unreachable policy asks for it
+                    let replacement = self.repair_route(&mut packet, actions)
+                        .or_else(|| self.alternate_route(next_hop, &mut packet));
+                    match replacement {
+                        Some(replacement) => next_hop = replacement,
+                        None => {
+                            // step 4.2: apply the unreachable policy, by default generate a nack
+                            // with NackType::ErrorInRouting and send it back to the source
+                            self.handle_unreachable(next_hop, packet, actions);
+                            return;
+                        }
+                    }
+                }
+
+                // check if the packet should be dropped
+                if droppable && self.is_dropped() {
+                    let mut dropped_message = packet.clone();
+                    dropped_message.routing_header.decrease_hop_index();
+                    actions.push(Action::Event(DroneEvent::PacketDropped(dropped_message)));
+
+                    self.generate_nack(
+                        NackType::Dropped,
+                        packet.get_fragment_index(),
+                        packet.routing_header,
+                        packet.session_id,
+                        actions
+                    );
+                    return;
+                }
+
+                if let PacketType::MsgFragment(fragment) = &mut packet.pack_type {
+                    self.flip_bits(fragment);
+                }
+                actions.push(Action::Forward(next_hop, packet));
+            }
+        }
+    }
+
+    fn flip_bits(&mut self, fragment: &mut Fragment) {
+        // This function flips each bit of the payload of the fragment
+        // with the probability given by the bit error rate
+        // ### Parameters:
+        // - `fragment`: The fragment to be forwarded
+
+        if self.bit_error_rate <= 0.0 {
+            return;
+        }
+        let length = (fragment.length as usize).min(fragment.data.len());
+        let mut flipped = 0;
+        for byte in fragment.data[..length].iter_mut() {
+            for bit in 0..8 {
+                if self.rng.gen_bool(self.bit_error_rate) {
+                    *byte ^= 1 << bit;
+                    flipped += 1;
+                }
+            }
+        }
+        if flipped > 0 {
+            debug!("Drone {} flipped {} bits of fragment {}", self.id, flipped, fragment.fragment_index);
+            self.stats.add_bits_flipped(flipped);
+        }
+    }
+
+    fn repair_route(&self, packet: &mut Packet, actions: &mut Vec<Action>) -> Option<NodeId> {
+        // This function splices a neighbour in the route of the packet, in place of
+        // the missing next hop, if it is known to be linked to the hop after it.
+        // If the hop after the missing one is a neighbour, the missing hop is removed
+        // ### Parameters:
+        // - `packet`: The packet, with the hop index already increased
+        //
+        // ### Returns:
+        // - `Option<NodeId>`: The new next hop, None if the route could not be repaired
+
+        let topology = self.topology.as_ref().filter(|_| self.local_repair)?;
+        let srh = &mut packet.routing_header;
+        let missing = srh.current_hop()?;
+        let after = srh.next_hop()?;
+
+        let replacement = if self.neighbours.contains(&after) {
+            srh.hops.remove(srh.hop_index);
+            None
+        } else {
+            let mut candidates = self.neighbours
+                .iter()
+                .copied()
+                .filter(|neighbour| !srh.hops.contains(neighbour) && topology.are_linked(*neighbour, after))
+                .collect::<Vec<_>>();
+            candidates.sort();
+            let replacement = *candidates.first()?;
+            srh.hops[srh.hop_index] = replacement;
+            Some(replacement)
+        };
+
+        info!("Drone {} repaired route around {}: {:?}", self.id, missing, srh.hops);
+        self.stats.add_route_repair();
+        actions.push(Action::Report(DroneReport::RouteRepaired {
+            drone: self.id,
+            session_id: packet.session_id,
+            missing,
+            replacement,
+        }));
+        Some(replacement.unwrap_or(after))
+    }
+
+    fn handle_unreachable(&self, next_hop: NodeId, packet: Packet, actions: &mut Vec<Action>) {
+        // This function handles a packet whose next hop is not a neighbour,
+        // applying the action of the unreachable policy for the packet type
+        // The alternate route has already been tried by `alternate_route`
+        // ### Parameters:
+        // - `next_hop`: The hop that cannot be reached
+        // - `packet`: The packet, with the hop index already increased
+
+        match self.unreachable_policy.action(&packet.pack_type) {
+            UnreachableAction::Shortcut => {
+                info!("Packet sent to controller");
+                actions.push(Action::Event(DroneEvent::ControllerShortcut(packet)));
+            },
+            UnreachableAction::Nack => {
+                self.generate_nack(
+                    NackType::ErrorInRouting(next_hop),
+                    packet.get_fragment_index(),
+                    packet.routing_header.clone(),
+                    packet.session_id,
+                    actions
+                );
+            },
+            UnreachableAction::Drop => {
+                info!("Drone {} dropping packet for unreachable hop {}", self.id, next_hop);
+                actions.push(Action::Drop(packet));
+            },
+            UnreachableAction::Alternate => self.refuse_route(next_hop, packet, actions),
+        }
+    }
+
+    fn alternate_route(&self, next_hop: NodeId, packet: &mut Packet) -> Option<NodeId> {
+        // This function replaces the rest of the route of the packet with the route
+        // learned to its destination, if the unreachable policy asks for it. The
+        // learned route must start from another neighbour and must not go back
+        // through the hops already travelled, so that the drone does not create a loop
+        // ### Parameters:
+        // - `next_hop`: The hop that cannot be reached
+        // - `packet`: The packet, with the hop index already increased
+        //
+        // ### Returns:
+        // - `Option<NodeId>`: The new next hop, None if the packet cannot be rerouted
+
+        if self.unreachable_policy.action(&packet.pack_type) != UnreachableAction::Alternate {
+            return None;
+        }
+        let srh = &mut packet.routing_header;
+        let travelled = srh.hops.get(..srh.hop_index)?;
+        let route = srh.hops
+            .last()
+            .and_then(|destination| self.routes.get(destination))
+            .filter(|route| route.first().is_some_and(|hop| *hop != next_hop && self.neighbours.contains(hop)))
+            .filter(|route| !route.iter().any(|hop| travelled.contains(hop)))?;
+
+        // keep the hops up to this drone and replace the rest of the route
+        srh.hops.truncate(srh.hop_index);
+        srh.hops.extend_from_slice(route);
+        info!("Drone {} rerouting packet around {} through {:?}", self.id, next_hop, route);
+        Some(route[0])
+    }
+
+    fn refuse_route(&self, next_hop: NodeId, packet: Packet, actions: &mut Vec<Action>) {
+        // This function handles a packet that cannot follow its route
+        // Acks, nacks and flood responses are sent to the controller, while
+        // a nack of type NackType::ErrorInRouting is sent back for the other packets
+        // ### Parameters:
+        // - `next_hop`: The hop that cannot be reached
+        // - `packet`: The packet, with the hop index already increased
+
+        match packet.pack_type {
+            PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_) => {
+                info!("Packet sent to controller");
+                actions.push(Action::Event(DroneEvent::ControllerShortcut(packet)));
+            },
+            _ => {
+                self.generate_nack(
+                    NackType::ErrorInRouting(next_hop),
+                    packet.get_fragment_index(),
+                    packet.routing_header.clone(),
+                    packet.session_id,
+                    actions
+                );
+            }
+        }
+    }
+
+    fn is_allowed(&self, packet: &Packet, actions: &mut Vec<Action>) -> bool {
+        // This function checks the source and the destination of the packet against
+        // the ACL of the drone, and applies the response of the ACL if it is denied
+        // ### Parameters:
+        // - `packet`: The packet to be checked, for this drone
+        //
+        // ### Returns:
+        // - `bool`: True if the packet can be relayed, false otherwise
+
+        let srh = &packet.routing_header;
+        let (Some(source), Some(destination)) = (srh.hops.first().copied(), srh.hops.last().copied()) else {
+            return true;
+        };
+        if self.acl.allows(source, destination) {
+            return true;
+        }
+
+        warn!("Drone {} denied packet from {} to {}", self.id, source, destination);
+        self.stats.add_packet_denied();
+        match (self.acl.response, &packet.pack_type) {
+            (AclResponse::Nack, PacketType::MsgFragment(_)) => {
+                // the nack is generated as if the packet was going to be forwarded,
+                // so that its route includes this drone
+                let mut srh = packet.routing_header.clone();
+                srh.increase_hop_index();
+                self.generate_nack(
+                    NackType::ErrorInRouting(self.id),
+                    packet.get_fragment_index(),
+                    srh,
+                    packet.session_id,
+                    actions
+                );
+            },
+            (AclResponse::Report, PacketType::MsgFragment(_)) => {
+                actions.push(Action::Report(DroneReport::PacketDenied {
+                    drone: self.id,
+                    session_id: packet.session_id,
+                    source,
+                    destination,
+                }));
+                actions.push(Action::Drop(packet.clone()));
+            },
+            (_, _) => {
+                // acks, nacks and flood responses must not be lost,
+                // they are given to the controller whatever the response
+                actions.push(Action::Report(DroneReport::PacketDenied {
+                    drone: self.id,
+                    session_id: packet.session_id,
+                    source,
+                    destination,
+                }));
+                actions.push(Action::Event(DroneEvent::ControllerShortcut(packet.clone())));
+            },
+        }
+        false
+    }
+
+    fn learn_routes(&mut self, srh: &SourceRoutingHeader) {
+        // This function learns the routes to the nodes already traversed by the
+        // packet: they can be reached by following the route backwards
+        // ### Parameters:
+        // - `srh`: The source routing header of a packet for this drone
+
+        if validate_header(srh).is_err() {
+            return;
+        }
+        let Some(previous) = srh.hop_index.checked_sub(1).and_then(|i| srh.hops.get(i)) else {
+            return;
+        };
+        if !self.neighbours.contains(previous) {
+            return;
+        }
+
+        let mut route = srh.hops[..srh.hop_index].to_vec();
+        route.reverse();
+        for len in 1..=route.len() {
+            let destination = route[len - 1];
+            let known = self.routes.get(&destination).map_or(usize::MAX, Vec::len);
+            if len <= known {
+                self.routes.insert(destination, route[..len].to_vec());
+            }
+        }
+    }
+
+    fn handle_flood_request(
+        &mut self,
+        mut flood_request: FloodRequest,
+        session_id: u64,
+        actions: &mut Vec<Action>
+    ) {
+        // This function handles the flood request and forwards it to the neighbours
+        // If the drone has already seen the flood request, it generates a flood response
+        // or if the drone has no other neighbour other than
+        // the previous hop (the sender of the flood request), it generates a flood response
+        // or if the path trace reached the maximum length, it generates a flood response
+        // ### Parameters:
+        // - `flood_request`: The flood request
+        // - `session_id`: The session id of the packet
+
+        self.stats.add_flood_request();
+        let prev_hop = flood_request.path_trace
+            .last()
+            .map(|x| x.0)
+            .unwrap_or(flood_request.initiator_id);
+
+        flood_request.path_trace.push((self.id, NodeType::Drone));
+        if let Some(topology) = &mut self.topology {
+            self.topology_changed |= topology.learn_path(&flood_request.path_trace);
+        }
+
+
+        let flood_session = (flood_request.flood_id, flood_request.initiator_id);
+
+        let too_long = self.max_flood_path.is_some_and(|max| flood_request.path_trace.len() >= max);
+
+        if !self.flood_session.insert(flood_session) || self.neighbours.len() == 1 || too_long {
+            self.generate_flood_response(flood_request, session_id, actions);
+            return;
+        }
+
+        if !self.take_flood_token(flood_request.initiator_id) {
+            warn!("Drone {} throttling floods of {}", self.id, flood_request.initiator_id);
+            self.stats.add_flood_throttled();
+            actions.push(Action::Report(DroneReport::FloodThrottled {
+                drone: self.id,
+                initiator: flood_request.initiator_id,
+                flood_id: flood_request.flood_id,
+            }));
+            self.generate_flood_response(flood_request, session_id, actions);
+            return;
+        }
+
+        let srh = SourceRoutingHeader::new(vec![], 0);
+
+        let new_flood_request = Packet::new_flood_request(
+            srh,
+            session_id,
+            flood_request,
+        );
+
+        // iterate the neighbours in a fixed order, so that the sequence of
+        // events sent to the controller does not depend on the hash set layout
+        let mut neighbours = self.neighbours.iter().copied().collect::<Vec<_>>();
+        neighbours.sort();
+
+        for neighbor_id in neighbours {
+            if neighbor_id != prev_hop {
+                actions.push(Action::Forward(neighbor_id, new_flood_request.clone()));
+            }
+        }
+    }
+
+    fn take_flood_token(&mut self, initiator_id: NodeId) -> bool {
+        // This function takes a token from the bucket of the initiator
+        // ### Parameters:
+        // - `initiator_id`: The initiator of the flood request
+        //
+        // ### Returns:
+        // - `bool`: True if the flood request can be forwarded
+
+        let Some(limit) = self.flood_rate_limit else {
+            return true;
+        };
+        let now = self.clock;
+        self.flood_buckets
+            .entry(initiator_id)
+            .or_insert_with(|| TokenBucket::new(&limit, now))
+            .take(&limit, now)
+    }
+
+    fn generate_nack(
+        &self,
+        nack_type: NackType,
+        fragment_index: u64,
+        mut srh: SourceRoutingHeader,
+        session_id: u64,
+        actions: &mut Vec<Action>
+    ) {
+        // This function generates a nack of type: nack_type, and sends it to the next hop
+        // ### Parameters:
+        // - `nack_type`: The type of nack to be generated
+        // - `srh`: The source routing header of the packet
+        // - `session_id`: The session id of the packet
+
+        let nack = Nack {
+            fragment_index,
+            nack_type,
+        };
+        self.stats.add_nack(nack_type);
+
+        // if the route is malformed, send a nack to the controller
+        if srh.len() == 1 {
+            let new_nack = Packet::new_nack(
+                srh,
+                session_id,
+                nack,
+            );
+            actions.push(Action::Event(DroneEvent::ControllerShortcut(new_nack)));
+            return;
+        }
+
+        // take the hops already travelled by the packet, they are the route of the nack
+        // once reversed. `get` is used instead of `sub_route(...).unwrap()` because
+        // the hop index of a malformed header can be past the end of the hops
+        let travelled = match nack_type {
+            NackType::ErrorInRouting(_) | NackType::Dropped => {
+                srh.hops.get(..srh.hop_index).map(<[NodeId]>::to_vec)
+            },
+            NackType::UnexpectedRecipient(_) => {
+                // the packet reached this drone instead of the expected one
+                srh.hops.get(..srh.hop_index).map(|hops| {
+                    let mut hops = hops.to_vec();
+                    hops.push(self.id);
+                    hops
+                })
+            },
+            NackType::DestinationIsDrone => {
+                srh.hops.get(..=srh.hop_index).map(<[NodeId]>::to_vec)
+            }
+        };
+
+        let Some(mut hops) = travelled else {
+            error!(
+                "Drone {} could not send nack, hop index {} is out of the route {:?}",
+                self.id,
+                srh.hop_index,
+                srh.hops
+            );
+            actions.push(Action::Drop(Packet::new_nack(srh, session_id, nack)));
+            return;
+        };
+        hops.reverse();
+        srh = SourceRoutingHeader::new(hops, 1);
+
+        let new_nack = Packet::new_nack(
+            srh,
+            session_id,
+            nack,
+        );
+
+        // get the next hop (use current_hop() instead of next_hop() because
+        // the hop index is reset to 1)
+        match new_nack.routing_header.current_hop() {
+            Some(next_hop) if self.neighbours.contains(&next_hop) => {
+                actions.push(Action::Forward(next_hop, new_nack));
+            },
+            Some(_) => {
+                actions.push(Action::Event(DroneEvent::ControllerShortcut(new_nack)));
+            },
+            None => {
+                // the route has no hop before this drone
+                error!(
+                    "Drone {} could not send nack, no hop to send it back to in {:?}",
+                    self.id,
+                    new_nack.routing_header.hops
+                );
+                actions.push(Action::Drop(new_nack));
+            }
+        }
+    }
+
+    fn generate_flood_response(
+        &self,
+        flood_request: FloodRequest,
+        session_id: u64,
+        actions: &mut Vec<Action>
+    ) {
+        // This function generates a flood response and sends it to the next hop
+        // ### Parameters:
+        // - `flood_request`: The flood request
+        // - `session_id`: The session id of the packet
+
+        let mut route: Vec<_> = flood_request.path_trace
+            .clone()
+            .iter()
+            .map(|(id, _)| *id)
+            .rev()
+            .collect::<Vec<_>>();
+
+
+        if route.last() != Some(&flood_request.initiator_id){
+            route.push(flood_request.initiator_id);
+        }
+
+        let flood_response = FloodResponse {
+            flood_id: flood_request.flood_id,
+            path_trace: flood_request.path_trace,
+        };
+
+        let mut srh = SourceRoutingHeader::new(route, 0);
+        match srh.next_hop() {
+            None => {
+                self.generate_nack(
+                    NackType::DestinationIsDrone,
+                    0,
+                    srh,
+                    session_id,
+                    actions
+                );
+            },
+            Some(next_hop) => {
+                srh.increase_hop_index();
+                let new_flood_response = Packet::new_flood_response(
+                    srh,
+                    session_id,
+                    flood_response
+                );
+
+                if self.neighbours.contains(&next_hop) {
+                    actions.push(Action::Forward(next_hop, new_flood_response));
+                } else {
+                    actions.push(Action::Event(DroneEvent::ControllerShortcut(new_flood_response)));
+                }
+            }
+        }
+    }
+
+    fn is_correct_recipient(
+        &self,
+        fragment_index: u64,
+        srh: &SourceRoutingHeader,
+        session_id: u64,
+        actions: &mut Vec<Action>
+    ) -> bool {
+        // This function checks if the packet is for this drone
+        // If the packet is not for this drone, a nack of type
+        // NackType::UnexpectedRecipient is generated and sent back to the source
+        // ### Parameters:
+        // - `srh`: The source routing header of the packet
+        // - `session_id`: The session id of the packet
+        //
+        // ### Returns:
+        // - `bool`: True if the packet is for this drone, false otherwise
+
+        let current_hop = srh.current_hop();
+        if current_hop != Some(self.id) {
+            self.generate_nack(
+                NackType::UnexpectedRecipient(self.id),
+                fragment_index,
+                srh.clone(),
+                session_id,
+                actions
+            );
+            false
+        } else {
+            true
+        }
+    }
+
+    fn is_dropped(&mut self) -> bool {
+        let pdr = self.pdr();
+        let drop = self.rng.gen_range(0.0..1.0);
+        drop <= pdr
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+use std::fmt;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+
+/// Why a source routing header received by a drone cannot be trusted.
+///
+/// The response of the drone to each case is applied by `DroneCore::handle_packet`:
+/// - `Empty`: the packet is dropped and an error is logged, there is nobody to answer to
+/// - `IndexZero` and `IndexPastEnd`: the previous hop is unknown, so a fragment is dropped
+///   and an error is logged, while an ack, a nack or a flood response is sent to the
+///   controller as it is, since they must not be lost
+/// - `DuplicateConsecutiveHops`: the route asks a node to send the packet to itself,
+///   the packet is handled as if that hop was not a neighbour (`ErrorInRouting` nack
+///   for a fragment, `ControllerShortcut` otherwise)
+/// - `Loop`: the response is chosen with `LoopPolicy`, by default the packet is
+///   forwarded normally since a route through the same node twice is not wrong by itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MalformedHeader {
+    /// The route has no hop
+    Empty,
+    /// The hop index points to the source of the packet
+    IndexZero,
+    /// The hop index is past the last hop of the route
+    IndexPastEnd,
+    /// The same node appears twice in a row in the route
+    DuplicateConsecutiveHops(NodeId),
+    /// The route goes through the same node twice
+    Loop(NodeId),
+}
+
+impl fmt::Display for MalformedHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MalformedHeader::Empty => write!(f, "the route is empty"),
+            MalformedHeader::IndexZero => write!(f, "the hop index points to the source"),
+            MalformedHeader::IndexPastEnd => write!(f, "the hop index is past the end of the route"),
+            MalformedHeader::DuplicateConsecutiveHops(id) => write!(f, "node {} follows itself in the route", id),
+            MalformedHeader::Loop(id) => write!(f, "node {} appears twice in the route", id),
+        }
+    }
+}
+
+/// Classifies the source routing header of a packet received by a drone.
+/// The first problem found is returned, in the order of the `MalformedHeader` variants
+pub fn validate_header(srh: &SourceRoutingHeader) -> Result<(), MalformedHeader> {
+    if srh.hops.is_empty() {
+        return Err(MalformedHeader::Empty);
+    }
+    if srh.hop_index == 0 {
+        return Err(MalformedHeader::IndexZero);
+    }
+    if srh.hop_index >= srh.hops.len() {
+        return Err(MalformedHeader::IndexPastEnd);
+    }
+    if let Some(pair) = srh.hops.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(MalformedHeader::DuplicateConsecutiveHops(pair[0]));
+    }
+
+    let mut visited = HashSet::new();
+    match srh.hops.iter().find(|id| !visited.insert(**id)) {
+        Some(id) => Err(MalformedHeader::Loop(*id)),
+        None => Ok(()),
+    }
+}
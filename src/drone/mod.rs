@@ -1,22 +1,39 @@
-use std::collections::{HashMap, HashSet};
-use log::{warn, error, info};
+use std::collections::HashMap;
+use log::info;
 use wg_2024::drone::{Drone};
 use wg_2024::controller::{DroneCommand, DroneEvent};
 use wg_2024::network::NodeId;
 use wg_2024::packet::Packet;
 use crossbeam_channel::select_biased;
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{never, Receiver, Sender};
 use std::env;
+use std::time::Instant;
+pub mod acl;
+pub mod command;
+pub mod core;
+pub mod header;
+pub mod pdr_schedule;
+pub mod policy;
+pub mod rate_limit;
+pub mod report;
 pub mod rust_do_it;
+pub mod stats;
+pub mod topology;
+#[cfg(feature = "async")]
+pub mod async_drone;
+#[cfg(feature = "byzantine")]
+pub mod byzantine;
 #[derive(Debug)]
 pub struct RustDoIt {
-    id: NodeId,
     controller_send: Sender<DroneEvent>,                // Used to send events to the controller (receiver is in the controller)
     controller_recv: Receiver<DroneCommand>,            // Used to receive commands from the controller (sender is in the controller)
     packet_recv: Receiver<Packet>,                      // The receiving end of the channel for receiving packets from drones
     packet_send: HashMap<NodeId, Sender<Packet>>,       // Mapping of drone IDs to senders, allowing packets to be sent to specific drones
-    flood_session: HashSet<(u64, NodeId)>,
-    pdr: f32,
+    core: core::DroneCore,                              // Decision logic of the drone, free of any channel
+    report_send: Option<Sender<report::DroneReport>>,   // Used to send the reports that are not a `DroneEvent`, if set
+    report_tick: Receiver<Instant>,                     // Ticks at which the learned topology is reported, never by default
+    created: Instant,                                   // Origin of the clock of the drone
+    extended_recv: Receiver<command::RustDoItCommand>,  // Used to receive the commands that are not a `DroneCommand`, never by default
 }
 
 impl Drone for RustDoIt {
@@ -30,13 +47,15 @@ impl Drone for RustDoIt {
         pdr: f32,
     ) -> Self {
         Self {
-            id,
             controller_send,
             controller_recv,
             packet_recv,
+            core: core::DroneCore::new(id, packet_send.keys().copied(), pdr),
             packet_send,
-            flood_session: HashSet::new(),
-            pdr,
+            report_send: None,
+            report_tick: never(),
+            created: Instant::now(),
+            extended_recv: never(),
         }
     }
 
@@ -46,8 +65,9 @@ impl Drone for RustDoIt {
             // Use select_biased to handle incoming commands and packets in normal operation
             select_biased! {
                 recv(self.controller_recv) -> command => {
+                    self.set_clock(self.created.elapsed().as_millis() as u64);
                     if let Ok(command) = command {
-                        info!("Drone {} received command {:?}", self.id, command);
+                        info!("Drone {} received command {:?}", self.id(), command);
                         if matches!(command, DroneCommand::Crash) {
                             self.handle_command(command);
                             return;
@@ -55,12 +75,21 @@ impl Drone for RustDoIt {
                         self.handle_command(command);
                     }
                 },
+                recv(self.extended_recv) -> command => {
+                    self.set_clock(self.created.elapsed().as_millis() as u64);
+                    if let Ok(command) = command {
+                        info!("Drone {} received extended command {:?}", self.id(), command);
+                        self.handle_extended_command(command);
+                    }
+                },
                 recv(self.packet_recv) -> packet => {
+                    self.set_clock(self.created.elapsed().as_millis() as u64);
                     if let Ok(packet) = packet {
-                        info!("Drone {} received packet {:?}", self.id, packet);
+                        info!("Drone {} received packet {:?}", self.id(), packet);
                         self.handle_packet(packet);
                     }
-                }
+                },
+                recv(self.report_tick) -> _ => self.report_topology(),
             }
         }
     }
@@ -0,0 +1,127 @@
+use std::f64::consts::TAU;
+use std::fmt;
+use std::path::Path;
+use serde::Deserialize;
+
+/// A packet drop rate that changes with the clock of the drone (milliseconds for
+/// a running drone, rounds for a drone of a `Simulation`). The rate is clamped
+/// between 0 and 1 when it is evaluated
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PdrSchedule {
+    /// `before` until time `at`, then `after`
+    Step { before: f32, after: f32, at: u64 },
+    /// `from` until time `start`, then changes linearly up to `to`, reached at time `end`
+    Linear { from: f32, to: f32, start: u64, end: u64 },
+    /// `mean + amplitude * sin(2π (t + phase) / period)`
+    Sine {
+        mean: f32,
+        amplitude: f32,
+        period: u64,
+        #[serde(default)]
+        phase: u64,
+    },
+    /// The rate of the latest point at or before the time. Before the first
+    /// point, the rate of the first point applies
+    Piecewise { points: Vec<(u64, f32)> },
+}
+
+/// Why a piecewise schedule could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdrScheduleError {
+    /// The file could not be read, with the reason
+    Io(String),
+    /// A line is not a `<time> <pdr>` pair, with its number starting from 1
+    InvalidLine(usize),
+    /// A rate is not between 0 and 1, with the number of its line
+    PdrOutOfRange(usize),
+    /// The schedule has no point
+    Empty,
+}
+
+impl fmt::Display for PdrScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PdrScheduleError::Io(reason) => write!(f, "cannot read the schedule: {}", reason),
+            PdrScheduleError::InvalidLine(line) => write!(f, "line {} is not a <time> <pdr> pair", line),
+            PdrScheduleError::PdrOutOfRange(line) => write!(f, "the pdr at line {} is not between 0 and 1", line),
+            PdrScheduleError::Empty => write!(f, "the schedule has no point"),
+        }
+    }
+}
+
+impl std::error::Error for PdrScheduleError {}
+
+impl PdrSchedule {
+    /// Returns the packet drop rate at the given time
+    pub fn pdr_at(&self, now: u64) -> f32 {
+        let pdr = match self {
+            PdrSchedule::Step { before, after, at } => if now < *at { *before } else { *after },
+            PdrSchedule::Linear { from, to, start, end } => {
+                if now <= *start {
+                    *from
+                } else if now >= *end {
+                    *to
+                } else {
+                    let progress = (now - start) as f32 / (end - start) as f32;
+                    from + (to - from) * progress
+                }
+            },
+            PdrSchedule::Sine { mean, amplitude, period, phase } => {
+                if *period == 0 {
+                    *mean
+                } else {
+                    let angle = TAU * (now.wrapping_add(*phase) % period) as f64 / *period as f64;
+                    mean + amplitude * angle.sin() as f32
+                }
+            },
+            PdrSchedule::Piecewise { points } => points
+                .iter()
+                .filter(|(time, _)| *time <= now)
+                .max_by_key(|(time, _)| *time)
+                .or_else(|| points.iter().min_by_key(|(time, _)| *time))
+                .map(|(_, pdr)| *pdr)
+                .unwrap_or_default(),
+        };
+        pdr.clamp(0.0, 1.0)
+    }
+
+    /// Parses a piecewise schedule made of one `<time> <pdr>` pair per line, separated
+    /// by spaces or a comma. Empty lines and lines starting with `#` are skipped
+    pub fn piecewise_from_str(text: &str) -> Result<Self, PdrScheduleError> {
+        let mut points = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|field| !field.is_empty())
+                .collect::<Vec<_>>();
+            let [time, pdr] = fields[..] else {
+                return Err(PdrScheduleError::InvalidLine(index + 1));
+            };
+            let (Ok(time), Ok(pdr)) = (time.parse::<u64>(), pdr.parse::<f32>()) else {
+                return Err(PdrScheduleError::InvalidLine(index + 1));
+            };
+            if !(0.0..=1.0).contains(&pdr) {
+                return Err(PdrScheduleError::PdrOutOfRange(index + 1));
+            }
+            points.push((time, pdr));
+        }
+
+        if points.is_empty() {
+            return Err(PdrScheduleError::Empty);
+        }
+        points.sort_by_key(|(time, _)| *time);
+        Ok(PdrSchedule::Piecewise { points })
+    }
+
+    /// Reads a piecewise schedule from a file, see `piecewise_from_str`
+    pub fn piecewise_from_file(path: impl AsRef<Path>) -> Result<Self, PdrScheduleError> {
+        let text = std::fs::read_to_string(path).map_err(|err| PdrScheduleError::Io(err.to_string()))?;
+        Self::piecewise_from_str(&text)
+    }
+}
@@ -0,0 +1,68 @@
+use wg_2024::packet::PacketType;
+
+/// What a drone does with a packet whose route goes through the same node twice
+/// (e.g. `[1, 11, 12, 11, 21]`), see `MalformedHeader::Loop`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoopPolicy {
+    /// Forward the packet along its route, like any other packet
+    #[default]
+    Forward,
+    /// Refuse the route as if the repeated node was not reachable: a fragment
+    /// gets an `ErrorInRouting` nack naming the repeated node, an ack, a nack
+    /// or a flood response is sent to the controller
+    Nack,
+    /// Send any packet to the controller with `DroneEvent::ControllerShortcut`
+    Shortcut,
+}
+
+/// What a drone does with a packet when the next hop of its route is not a neighbour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnreachableAction {
+    /// Send the packet to the controller with `DroneEvent::ControllerShortcut`
+    Shortcut,
+    /// Send an `ErrorInRouting` nack back along the route
+    Nack,
+    /// Discard the packet without notifying anyone
+    Drop,
+    /// Rewrite the rest of the route through a neighbour known to reach the
+    /// destination, learned from the routes of the packets relayed by the drone.
+    /// Falls back to the behaviour of `UnreachablePolicy::default` when no route is known
+    Alternate,
+}
+
+/// The decision table applied when the next hop of a packet is not a neighbour,
+/// one entry per type of source routed packet.
+///
+/// The default table is the behaviour of the protocol: fragments get a nack,
+/// every other packet is sent to the controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnreachablePolicy {
+    pub fragment: UnreachableAction,
+    pub ack: UnreachableAction,
+    pub nack: UnreachableAction,
+    pub flood_response: UnreachableAction,
+}
+
+impl Default for UnreachablePolicy {
+    fn default() -> Self {
+        Self {
+            fragment: UnreachableAction::Nack,
+            ack: UnreachableAction::Shortcut,
+            nack: UnreachableAction::Shortcut,
+            flood_response: UnreachableAction::Shortcut,
+        }
+    }
+}
+
+impl UnreachablePolicy {
+    /// Returns the action for the given type of packet. Flood requests are not
+    /// source routed, they use the entry of the fragments
+    pub fn action(&self, pack_type: &PacketType) -> UnreachableAction {
+        match pack_type {
+            PacketType::MsgFragment(_) | PacketType::FloodRequest(_) => self.fragment,
+            PacketType::Ack(_) => self.ack,
+            PacketType::Nack(_) => self.nack,
+            PacketType::FloodResponse(_) => self.flood_response,
+        }
+    }
+}
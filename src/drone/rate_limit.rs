@@ -0,0 +1,46 @@
+/// The flood requests a drone forwards for each initiator: at most `burst` at once,
+/// then one more every `interval` units of the drone clock (milliseconds for a
+/// running drone, rounds for a drone of a `Simulation`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloodRateLimit {
+    pub burst: u32,
+    pub interval: u64,
+}
+
+/// The state of the token bucket of one initiator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TokenBucket {
+    tokens: u32,
+    refilled_at: u64,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket
+    pub(crate) fn new(limit: &FloodRateLimit, now: u64) -> Self {
+        Self { tokens: limit.burst, refilled_at: now }
+    }
+
+    /// Refills the bucket up to `now` and takes a token from it.
+    /// Returns false if the bucket is empty
+    pub(crate) fn take(&mut self, limit: &FloodRateLimit, now: u64) -> bool {
+        let elapsed = now.saturating_sub(self.refilled_at);
+        let refill = elapsed.checked_div(limit.interval).unwrap_or(u64::MAX);
+        if refill > 0 {
+            self.tokens = u64::from(self.tokens)
+                .saturating_add(refill)
+                .min(u64::from(limit.burst)) as u32;
+            // keep the remainder, unless the bucket is full
+            self.refilled_at = if self.tokens == limit.burst {
+                now
+            } else {
+                self.refilled_at + refill * limit.interval
+            };
+        }
+
+        if self.tokens == 0 {
+            return false;
+        }
+        self.tokens -= 1;
+        true
+    }
+}
@@ -0,0 +1,62 @@
+use log::{debug, info, warn};
+use wg_2024::network::NodeId;
+use super::topology::Topology;
+
+/// What a `RustDoIt` drone tells the controller beyond the `DroneEvent`s of the
+/// protocol. The reports are sent on the channel set with `RustDoIt::with_reports`,
+/// every report names the drone it comes from so that the channel can be shared
+#[derive(Debug, Clone, PartialEq)]
+pub enum DroneReport {
+    /// The drone changed the route of a packet to go around a neighbour it
+    /// has no channel to. `replacement` is the neighbour used instead of the
+    /// missing one, or None if the missing hop was simply removed from the route
+    RouteRepaired {
+        drone: NodeId,
+        session_id: u64,
+        missing: NodeId,
+        replacement: Option<NodeId>,
+    },
+    /// The topology learned by the drone from the relayed flood traffic,
+    /// sent periodically when it changed
+    Topology {
+        drone: NodeId,
+        topology: Topology,
+    },
+    /// A flood request was answered instead of forwarded, because its
+    /// initiator exceeded the flood rate limit of the drone
+    FloodThrottled {
+        drone: NodeId,
+        initiator: NodeId,
+        flood_id: u64,
+    },
+    /// A packet was discarded because the access control list of the drone
+    /// denies its source or its destination
+    PacketDenied {
+        drone: NodeId,
+        session_id: u64,
+        source: NodeId,
+        destination: NodeId,
+    },
+}
+
+impl DroneReport {
+    /// Logs a report that the drone has no channel to send on, so that the route
+    /// repairs, the throttled floods and the denied packets are not silently lost
+    pub(crate) fn log_unsent(&self) {
+        match self {
+            DroneReport::RouteRepaired { drone, session_id, missing, replacement } => info!(
+                "Drone {} repaired the route of session {} around {}, replacement {:?}",
+                drone, session_id, missing, replacement
+            ),
+            DroneReport::Topology { drone, topology } => debug!("Drone {} learned topology {:?}", drone, topology),
+            DroneReport::FloodThrottled { drone, initiator, flood_id } => warn!(
+                "Drone {} throttled flood {} of {}",
+                drone, flood_id, initiator
+            ),
+            DroneReport::PacketDenied { drone, session_id, source, destination } => warn!(
+                "Drone {} denied session {} from {} to {}",
+                drone, session_id, source, destination
+            ),
+        }
+    }
+}
@@ -1,448 +1,276 @@
 extern crate wg_2024;
 
-use crossbeam_channel::Sender;
-use log::{warn, error, debug, info};
-use rand::Rng;
+use std::sync::Arc;
+use std::time::Duration;
+use crossbeam_channel::{tick, Receiver, Sender};
+use log::{error, debug, warn};
 use wg_2024::controller::{DroneCommand, DroneEvent};
-use wg_2024::network::{NodeId, SourceRoutingHeader};
-use wg_2024::packet::{Nack, NackType, Packet, PacketType, NodeType, FloodRequest, FloodResponse};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{Packet, PacketType};
+use super::acl::Acl;
+#[cfg(feature = "byzantine")]
+use super::byzantine::ByzantineMode;
+use super::command::RustDoItCommand;
+use super::core::{Action, DroneCore};
+use super::pdr_schedule::PdrSchedule;
+use super::policy::{LoopPolicy, UnreachablePolicy};
+use super::rate_limit::FloodRateLimit;
+use super::report::DroneReport;
+use super::stats::DroneStats;
+use super::topology::Topology;
 use super::RustDoIt;
 
 
 impl RustDoIt {
 
-    pub fn handle_command(&mut self, command: DroneCommand) {
-        // This function handles the command received from the controller
-        // It checks the command type and takes the appropriate actions
-        // to handle the command
-        // ### Parameters:
-        // - `command`: The command to be handled
-
-        match command {
-            DroneCommand::AddSender(node_id, sender) => {
-                self.packet_send.insert(node_id, sender);
-                debug!("Drone {} added sender {}", self.id, node_id);
-            },
-
-            DroneCommand::SetPacketDropRate(pdr) => {
-                if (0.0..=1.0).contains(&pdr) {
-                    self.pdr = pdr;
-                    debug!("Drone {} set PDR to {}", self.id, self.pdr);
-                } else {
-                    warn!(
-                        "Drone {} could not set PDR to {}, value must be between 0.0 and 1.0",
-                        self.id,
-                        pdr
-                    );
-                }
-            },
-
-            DroneCommand::Crash => {
-                while let Ok(packet) = self.packet_recv.try_recv() {
-                    self.handle_packet_crash(packet);
-                    debug!("Drone {} crashed", self.id);
-                };
-            },
+    /// Reseeds the random number generator used to decide packet drops,
+    /// making the behaviour of the drone reproducible across runs
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.core = self.core.with_seed(seed);
+        self
+    }
 
-            DroneCommand::RemoveSender(node_id) => {
-                if let Some(_removed_sender) = self.packet_send.remove(&node_id) {
-                    debug!("Drone {} removed sender {}", self.id, node_id);
-                } else {
-                    warn!(
-                        "Drone {} could not remove sender {} because it is not a neighbour",
-                        self.id,
-                        node_id
-                    );
-                }
-            },
-        }
+    /// Sets what the drone does with routes going through the same node twice
+    pub fn with_loop_policy(mut self, loop_policy: LoopPolicy) -> Self {
+        self.core = self.core.with_loop_policy(loop_policy);
+        self
     }
 
+    /// Sets what the drone does with packets whose next hop is not a neighbour
+    pub fn with_unreachable_policy(mut self, unreachable_policy: UnreachablePolicy) -> Self {
+        self.core = self.core.with_unreachable_policy(unreachable_policy);
+        self
+    }
 
-    pub fn handle_packet(&mut self, packet: Packet) {
-        // This function handles the received packet
-        // It checks the packet type and calls the appropriate function
-        // to handle the packet
-        // ### Parameters:
-        // - `packet`: The packet to be handled
+    /// Enables or disables the local repair of the routes around missing neighbours,
+    /// see `DroneCore::with_local_repair`
+    pub fn with_local_repair(mut self, local_repair: bool) -> Self {
+        self.core = self.core.with_local_repair(local_repair);
+        self
+    }
 
-        match packet.pack_type {
-            PacketType::FloodRequest(flood_request) => self.handle_flood_request(
-                flood_request,
-                packet.session_id
-            ),
+    /// Sets the channel on which the drone sends its `DroneReport`s.
+    /// Without it the reports are only logged
+    pub fn with_reports(mut self, report_send: Sender<DroneReport>) -> Self {
+        self.report_send = Some(report_send);
+        self
+    }
 
-            _ => self.check_packet(packet)
+    /// Sets the maximum length of the path trace of the flood requests,
+    /// see `DroneCore::with_max_flood_path`
+    pub fn with_max_flood_path(mut self, max_flood_path: usize) -> Self {
+        self.core = self.core.with_max_flood_path(max_flood_path);
+        self
+    }
 
-        }
+    /// Limits the flood requests forwarded for each initiator,
+    /// see `DroneCore::with_flood_rate_limit`
+    pub fn with_flood_rate_limit(mut self, flood_rate_limit: FloodRateLimit) -> Self {
+        self.core = self.core.with_flood_rate_limit(flood_rate_limit);
+        self
     }
 
-    /// This function handles the packet in case of a crash
-    fn handle_packet_crash(&mut self, packet: Packet) {
-        match packet.pack_type {
-            PacketType::MsgFragment(_) => {
-                self.generate_nack(
-                    NackType::ErrorInRouting(self.id),
-                    packet.get_fragment_index(),
-                    packet.routing_header.clone(),
-                    packet.session_id
-                );
-                if self.controller_send.send(DroneEvent::PacketDropped(packet)).is_err() {
-                    error!("Drone {} could not send packet to controller", self.id);
-                }
-            },
+    /// Sets the sources and destinations the drone relays packets for
+    pub fn with_acl(mut self, acl: Acl) -> Self {
+        self.core = self.core.with_acl(acl);
+        self
+    }
 
-            PacketType::FloodRequest(_) => {
-                self.generate_nack(
-                    NackType::ErrorInRouting(self.id),
-                    packet.get_fragment_index(),
-                    packet.routing_header.clone(),
-                    packet.session_id
-                );
-                let result = self.controller_send
-                    .send(DroneEvent::PacketDropped(packet));
-
-                if result.is_err() {
-                    error!("Drone {} could not send packet to controller", self.id);
-                }
-            },
+    /// Sets the channel on which the drone receives its `RustDoItCommand`s,
+    /// with a lower priority than the `DroneCommand`s
+    pub fn with_extended_commands(mut self, extended_recv: Receiver<RustDoItCommand>) -> Self {
+        self.extended_recv = extended_recv;
+        self
+    }
 
-            _ => self.check_packet(packet)
+    /// Makes the packet drop rate follow a schedule evaluated against the logical clock,
+    /// until the next `DroneCommand::SetPacketDropRate`
+    pub fn with_pdr_schedule(mut self, pdr_schedule: PdrSchedule) -> Self {
+        self.core = self.core.with_pdr_schedule(pdr_schedule);
+        self
+    }
 
-        }
+    /// Makes the drone misbehave in the given way, see `ByzantineMode`.
+    /// Only meant for adversarial testing
+    #[cfg(feature = "byzantine")]
+    pub fn with_byzantine_mode(mut self, mode: ByzantineMode) -> Self {
+        self.core = self.core.with_byzantine_mode(mode);
+        self
     }
 
-    fn check_packet(&self, mut packet: Packet) {
-        // This function is responsible for checking the packet before forwarding it.
-        // It checks if the packet is for this drone and
-        // if the next hop is in the list of neighbours
-        // if the next hop is not in the list of neighbours,
-        // it generates a nack of type NackType::ErrorInRouting
-        // else increases the hop index and forwards the packet to the next hop
-        // ### Parameters:
-        // - `packet`: The packet to be forwarded
+    /// Makes the drone flip bits of the fragments it forwards,
+    /// see `DroneCore::with_bit_error_rate`
+    pub fn with_bit_error_rate(mut self, bit_error_rate: f64) -> Self {
+        self.core = self.core.with_bit_error_rate(bit_error_rate);
+        self
+    }
 
-        // Step 0: check if the packet is droppable or not
-        let droppable = matches!(packet.pack_type, PacketType::MsgFragment(_));
+    /// Sets the logical time of the drone, in the unit of the flood rate limit.
+    /// `run` sets it to the milliseconds elapsed since the drone was created
+    pub fn set_clock(&mut self, now: u64) {
+        self.core.set_clock(now);
+    }
 
-        // Step 1: Check if the packet is for this drone
-        if !self.is_correct_recipient(
-            packet.get_fragment_index(),
-            &packet.routing_header,
-            packet.session_id
-        ) {
-            return;
-        }
+    /// Enables or disables the learning of the topology from the relayed flood traffic
+    pub fn with_topology_learning(mut self, learning: bool) -> Self {
+        self.core = self.core.with_topology_learning(learning);
+        self
+    }
 
-        // Step 2: Check if there is a next node
-        match packet.routing_header.next_hop() {
-            None => {
-                self.generate_nack(
-                    NackType::DestinationIsDrone,
-                    packet.get_fragment_index(),
-                    packet.routing_header,
-                    packet.session_id
-                );
+    /// Enables the learning of the topology and sends a `DroneReport::Topology`
+    /// on the report channel every `interval`, if the topology changed
+    pub fn with_topology_reports(mut self, interval: Duration) -> Self {
+        self.core = self.core.with_topology_learning(true);
+        self.report_tick = tick(interval);
+        self
+    }
 
-            },
-            Some(next_hop) => {
-                // Step 3: increase the hop index
-                packet.routing_header.increase_hop_index();
+    /// Returns the topology learned so far, None if the learning is off
+    pub fn topology(&self) -> Option<&Topology> {
+        self.core.topology()
+    }
 
-                // Step 4: Check if the next hop is in the list of neighbours
-                let next_hop = match self.packet_send.get(&next_hop) {
-                    Some(sender) => sender,
-                    None => {
-                        // step 4.1: if the next hop is not in the list of neighbours, generate a nack with
-                        // NackType::ErrorInRouting and send it back to the source
-                        warn!("Drone {} not found in the list of neighbours, probably crashed.", &next_hop);
-                        match packet.pack_type {
-                            PacketType::Ack(_) | PacketType::Nack(_) | PacketType::FloodResponse(_) => {
-                                info!("Packet sent to controller");
-                                self.controller_send.send(DroneEvent::ControllerShortcut(packet.clone())).ok();
-                                return;
-                            },
-                            _ => {
-                                self.generate_nack(
-                                    NackType::ErrorInRouting(next_hop),
-                                    packet.get_fragment_index(),
-                                    packet.routing_header.clone(),
-                                    packet.session_id
-                                );
-                                return;
-                            }
-                        }
-                    }
-                };
+    /// Sends the learned topology on the report channel, if it changed since the last report
+    pub fn report_topology(&mut self) {
+        let actions = self.core.topology_report();
+        self.execute(actions);
+    }
 
-                // check if the packet should be dropped
-                if droppable && self.is_dropped() {
-                    let mut dropped_message = packet.clone();
-                    dropped_message.routing_header.decrease_hop_index();
-                    if self.controller_send
-                        .send(DroneEvent::PacketDropped(dropped_message))
-                        .is_err() {
-                        error!("Drone {} could not send packet to controller", self.id);
-                    }
+    /// Makes the drone update the given counters instead of its own
+    pub fn with_stats(mut self, stats: Arc<DroneStats>) -> Self {
+        self.core = self.core.with_stats(stats);
+        self
+    }
 
-                    self.generate_nack(
-                        NackType::Dropped,
-                        packet.get_fragment_index(),
-                        packet.routing_header,
-                        packet.session_id,
-                    );
-                    return;
-                }
+    /// Returns the counters of the drone. The returned handle can be kept
+    /// after the drone is moved to its thread
+    pub fn stats(&self) -> Arc<DroneStats> {
+        self.core.stats()
+    }
 
-                self.forward_packet(packet, &next_hop);
-            }
-        }
+    /// Returns the id of the drone
+    pub fn id(&self) -> NodeId {
+        self.core.id()
     }
 
+    /// Returns the decision logic of the drone
+    pub fn core(&self) -> &DroneCore {
+        &self.core
+    }
 
-    fn handle_flood_request(
-        &mut self,
-        mut flood_request: FloodRequest,
-        session_id: u64
-    ) {
-        // This function handles the flood request and forwards it to the neighbours
-        // If the drone has already seen the flood request, it generates a flood response
-        // or if the drone has no other neighbour other than
-        // the previous hop (the sender of the flood request), it generates a flood response
+    pub fn handle_command(&mut self, command: DroneCommand) {
+        // This function handles the command received from the controller
+        // The state of the drone is updated by the core, while the senders
+        // and the pending packets are managed here
         // ### Parameters:
-        // - `flood_request`: The flood request
-        // - `srh`: The source routing header of the packet
-        // - `session_id`: The session id of the packet
-
-        let prev_hop = flood_request.path_trace
-            .last()
-            .map(|x| x.0)
-            .unwrap_or(flood_request.initiator_id);
-
-        flood_request.path_trace.push((self.id, NodeType::Drone));
-
-
-        let flood_session = (flood_request.flood_id, flood_request.initiator_id);
+        // - `command`: The command to be handled
 
-        if !self.flood_session.insert(flood_session) || self.packet_send.len() == 1 {
-            self.generate_flood_response(flood_request, session_id);
-            return;
-        }
+        let actions = self.core.handle_command(&command);
+        self.execute(actions);
 
-        let srh = SourceRoutingHeader::new(vec![], 0);
+        match command {
+            DroneCommand::AddSender(node_id, sender) => {
+                self.packet_send.insert(node_id, sender);
+            },
 
-        let new_flood_request = Packet::new_flood_request(
-            srh,
-            session_id,
-            flood_request,
-        );
+            DroneCommand::RemoveSender(node_id) => {
+                self.packet_send.remove(&node_id);
+            },
 
-        for (neighbor_id, neighbor) in self.packet_send.iter() {
-            if *neighbor_id != prev_hop {
-                let result = match neighbor.send(new_flood_request.clone()) {
-                    Ok(_) => self.controller_send.send(DroneEvent::PacketSent(new_flood_request.clone())),
-                    Err(_) => self.controller_send.send(DroneEvent::ControllerShortcut(new_flood_request.clone())),
+            DroneCommand::Crash => {
+                while let Ok(packet) = self.packet_recv.try_recv() {
+                    let actions = self.core.handle_packet_crash(packet);
+                    self.execute(actions);
                 };
+            },
 
-                if result.is_err() {
-                    error!("Drone {} could not send packet to controller", self.id);
-                }
-            }
+            DroneCommand::SetPacketDropRate(_) => {},
         }
     }
 
-    fn generate_nack(
-        &self,
-        nack_type: NackType,
-        fragment_index: u64,
-        mut srh: SourceRoutingHeader,
-        session_id: u64
-    ) {
-        // This function generates a nack of type: nack_type, and sends it to the next hop
-        // ### Parameters:
-        // - `nack_type`: The type of nack to be generated
-        // - `srh`: The source routing header of the packet
-        // - `session_id`: The session id of the packet
-
-        let nack = Nack {
-            fragment_index,
-            nack_type,
-        };
-
-        // if the route is malformed, send a nack to the controller
-        if srh.len() == 1 {
-            let new_nack = Packet::new_nack(
-                srh,
-                session_id,
-                nack,
-            );
-            if self.controller_send.send(DroneEvent::ControllerShortcut(new_nack)).is_err() {
-                error!("Drone {} could not send packet to controller", self.id);
-            }
-            return;
-        }
-
-        match nack_type {
-            NackType::ErrorInRouting(_) | NackType::Dropped => {
-                // reverse the trace
-                srh = srh.sub_route(0..srh.hop_index).unwrap();
-                srh.hops.reverse();
-                srh.hop_index = 1;
-            },
-            NackType::UnexpectedRecipient(_) => {
-                // reverse the trace
-                srh = srh.sub_route(0..=srh.hop_index).unwrap();
-                srh.hops.pop();
-                srh.hops.push(self.id);
-                srh.hops.reverse();
-                srh.hop_index = 1;
-            },
-            NackType::DestinationIsDrone => {
-                // reverse the trace
-                srh = srh.sub_route(0..=srh.hop_index).unwrap();
-                srh.hops.reverse();
-                srh.hop_index = 1;
-            }
-        }
 
-        let new_nack = Packet::new_nack(
-            srh,
-            session_id,
-            nack,
-        );
-
-        // get the next hop (use current_hop() instead of next_hop() because
-        // the hop index is reset to 1)
-        let next_hop = new_nack.routing_header.current_hop().unwrap();
-        let next_hop = match self.packet_send.get(&next_hop) {
-            None => {
-                self.controller_send.send(DroneEvent::ControllerShortcut(new_nack)).ok();
-                return;
-            }
-            Some(hop) => hop
-        };
-
-        // send nack to next hop, if send fails, send to controller
-        self.forward_packet(new_nack, &next_hop);
+    pub fn handle_extended_command(&mut self, command: RustDoItCommand) {
+        // This function handles a command that is not part of the protocol
+        // ### Parameters:
+        // - `command`: The command to be handled
 
+        let actions = self.core.handle_extended_command(&command);
+        self.execute(actions);
     }
 
-    fn generate_flood_response(&self, flood_request: FloodRequest, session_id: u64) {
-        // This function generates a flood response and sends it to the next hop
+    pub fn handle_packet(&mut self, packet: Packet) {
+        // This function handles the received packet
+        // The core decides what to do with the packet and
+        // the resulting actions are executed on the channels
         // ### Parameters:
-        // - `flood_request`: The flood request
-        // - `session_id`: The session id of the packet
-
-        let mut route: Vec<_> = flood_request.path_trace
-            .clone()
-            .iter()
-            .map(|(id, _)| *id)
-            .rev()
-            .collect::<Vec<_>>();
+        // - `packet`: The packet to be handled
 
+        let actions = self.core.handle_packet(packet);
+        self.execute(actions);
+    }
 
-        if route.last() != Some(&flood_request.initiator_id){
-            route.push(flood_request.initiator_id);
-        }
+    fn execute(&self, actions: Vec<Action>) {
+        // This function executes the actions decided by the core, in order
+        // ### Parameters:
+        // - `actions`: The actions to be executed
 
-        let flood_response = FloodResponse {
-            flood_id: flood_request.flood_id,
-            path_trace: flood_request.path_trace,
-        };
-
-        let mut srh = SourceRoutingHeader::new(route, 0);
-        match &srh.next_hop() {
-            None => {
-                self.generate_nack(
-                    NackType::DestinationIsDrone,
-                    0,
-                    srh,
-                    session_id
-                );
-            },
-            Some(next_hop) => {
-                srh.increase_hop_index();
-                let new_flood_response = Packet::new_flood_response(
-                    srh,
-                    session_id,
-                    flood_response
-                );
-
-                match self.packet_send.get(&next_hop) {
+        for action in actions {
+            match action {
+                Action::Forward(next_hop, packet) => match self.packet_send.get(&next_hop) {
+                    Some(sender) => self.forward_packet(packet, sender),
                     None => {
-                        self.controller_send.send(DroneEvent::ControllerShortcut(new_flood_response)).ok();
-                        return;
+                        warn!("Drone {} has no sender for {}", self.id(), next_hop);
+                        self.report_failed_send(packet);
                     }
-                    Some(hop) => self.forward_packet(new_flood_response, hop)
-                }
+                },
+                Action::Event(event) => self.send_event(event),
+                Action::Drop(packet) => {
+                    debug!("Drone {} discarded packet {:?}", self.id(), packet);
+                },
+                Action::Report(report) => self.send_report(report),
             }
         }
     }
 
-    fn is_correct_recipient(
-        &self,
-        fragment_index: u64,
-        srh: &SourceRoutingHeader,
-        session_id: u64
-    ) -> bool {
-        // This function checks if the packet is for this drone
-        // If the packet is not for this drone, a nack of type
-        // NackType::UnexpectedRecipient is generated and sent back to the source
-        // ### Parameters:
-        // - `srh`: The source routing header of the packet
-        // - `session_id`: The session id of the packet
-        //
-        // ### Returns:
-        // - `bool`: True if the packet is for this drone, false otherwise
-
-        let current_hop = srh.current_hop();
-        if current_hop != Some(self.id) {
-            self.generate_nack(
-                NackType::UnexpectedRecipient(self.id),
-                fragment_index,
-                srh.clone(),
-                session_id
-            );
-            false
-        } else {
-            true
-        }
-    }
-
-    fn is_dropped(&self) -> bool {
-        let drop = rand::thread_rng().gen_range(0.0..1.0);
-        drop <= self.pdr
-    }
-
     fn forward_packet(
         &self,
         packet: Packet,
         next_hop: &Sender<Packet>,
     ) {
         // This function is responsible for forwarding the packet to the next hop
-        // If the next hop is not in the list of neighbours, a nack is generated
-        // and sent back to the source
+        // If the send fails, the packet is reported to the controller
         // ### Parameters:
         // - `packet`: The packet to be forwarded
-        // - `next_hop`: The next hop id to which the packet should be forwarded
+        // - `next_hop`: The sender of the next hop to which the packet should be forwarded
 
         if next_hop.send(packet.clone()).is_ok() {
-            // if the send fails, log an error
-            if self.controller_send.send(DroneEvent::PacketSent(packet)).is_err() {
-                error!("Drone {} could not send packet to controller", self.id);
-            }
+            self.send_event(DroneEvent::PacketSent(packet));
         } else {
-            let result = {
-                if let PacketType::MsgFragment(_) = packet.pack_type {
-                    self.controller_send.send(DroneEvent::PacketDropped(packet))
-                } else {
-                    self.controller_send.send(DroneEvent::ControllerShortcut(packet))
-                }
-            };
-
-            if result.is_err() {
-                error!("Drone {} could not send packet to controller", self.id);
-            }
+            self.report_failed_send(packet);
+        }
+    }
+
+    fn report_failed_send(&self, packet: Packet) {
+        if let PacketType::MsgFragment(_) = packet.pack_type {
+            self.send_event(DroneEvent::PacketDropped(packet));
+        } else {
+            self.send_event(DroneEvent::ControllerShortcut(packet));
+        }
+    }
+
+    fn send_event(&self, event: DroneEvent) {
+        self.core.counters().add_event(&event);
+        if self.controller_send.send(event).is_err() {
+            error!("Drone {} could not send packet to controller", self.id());
+        }
+    }
+
+    fn send_report(&self, report: DroneReport) {
+        match &self.report_send {
+            Some(report_send) => if report_send.send(report).is_err() {
+                error!("Drone {} could not send report to controller", self.id());
+            },
+            None => report.log_unsent(),
         }
     }
 }
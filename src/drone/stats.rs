@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use wg_2024::controller::DroneEvent;
+use wg_2024::packet::NackType;
+
+/// Counters of the notable events seen by a drone.
+///
+/// The counters are shared through an `Arc`, so they can be read by the owner
+/// of the drone while the drone runs in its own thread
+#[derive(Debug, Default)]
+pub struct DroneStats {
+    packets_forwarded: AtomicU64,
+    packets_dropped: AtomicU64,
+    controller_shortcuts: AtomicU64,
+    flood_requests: AtomicU64,
+    nacks: [AtomicU64; 4],
+    routing_loops: AtomicU64,
+    route_repairs: AtomicU64,
+    floods_throttled: AtomicU64,
+    packets_denied: AtomicU64,
+    bits_flipped: AtomicU64,
+}
+
+impl DroneStats {
+    /// Returns the number of packets sent to a neighbour
+    pub fn packets_forwarded(&self) -> u64 {
+        self.packets_forwarded.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of fragments dropped, by the PDR or because the send failed
+    pub fn packets_dropped(&self) -> u64 {
+        self.packets_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of packets sent to the controller with `DroneEvent::ControllerShortcut`
+    pub fn controller_shortcuts(&self) -> u64 {
+        self.controller_shortcuts.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of flood requests received
+    pub fn flood_requests(&self) -> u64 {
+        self.flood_requests.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of nacks of the given type generated by the drone.
+    /// The id carried by the nack type is ignored
+    pub fn nacks(&self, nack_type: NackType) -> u64 {
+        self.nacks[nack_index(nack_type)].load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of nacks of any type generated by the drone
+    pub fn nacks_total(&self) -> u64 {
+        self.nacks.iter().map(|counter| counter.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Counts an event sent to the controller
+    pub(crate) fn add_event(&self, event: &DroneEvent) {
+        let counter = match event {
+            DroneEvent::PacketSent(_) => &self.packets_forwarded,
+            DroneEvent::PacketDropped(_) => &self.packets_dropped,
+            DroneEvent::ControllerShortcut(_) => &self.controller_shortcuts,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_flood_request(&self) {
+        self.flood_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_nack(&self, nack_type: NackType) {
+        self.nacks[nack_index(nack_type)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of packets received with a route going through the same node twice
+    pub fn routing_loops(&self) -> u64 {
+        self.routing_loops.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add_routing_loop(&self) {
+        self.routing_loops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of routes repaired around a missing neighbour
+    pub fn route_repairs(&self) -> u64 {
+        self.route_repairs.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add_route_repair(&self) {
+        self.route_repairs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of flood requests answered instead of forwarded because of the rate limit
+    pub fn floods_throttled(&self) -> u64 {
+        self.floods_throttled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add_flood_throttled(&self) {
+        self.floods_throttled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of packets denied by the access control list
+    pub fn packets_denied(&self) -> u64 {
+        self.packets_denied.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add_packet_denied(&self) {
+        self.packets_denied.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of bits flipped in the forwarded fragments by the bit error rate
+    pub fn bits_flipped(&self) -> u64 {
+        self.bits_flipped.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add_bits_flipped(&self, bits: u64) {
+        self.bits_flipped.fetch_add(bits, Ordering::Relaxed);
+    }
+}
+
+fn nack_index(nack_type: NackType) -> usize {
+    match nack_type {
+        NackType::ErrorInRouting(_) => 0,
+        NackType::DestinationIsDrone => 1,
+        NackType::Dropped => 2,
+        NackType::UnexpectedRecipient(_) => 3,
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+
+/// A partial, undirected map of the network, learned from the `path_trace`s
+/// of the flood requests and flood responses relayed by a drone
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Topology {
+    nodes: BTreeMap<NodeId, NodeType>,
+    links: BTreeMap<NodeId, BTreeSet<NodeId>>,
+}
+
+impl Topology {
+    /// Adds the nodes of the path trace and the links between every two
+    /// consecutive nodes. Returns true if anything new was learned
+    pub fn learn_path(&mut self, path_trace: &[(NodeId, NodeType)]) -> bool {
+        let mut changed = false;
+        for (id, node_type) in path_trace.iter() {
+            changed |= self.nodes.insert(*id, *node_type) != Some(*node_type);
+        }
+        for pair in path_trace.windows(2) {
+            let (a, b) = (pair[0].0, pair[1].0);
+            if a != b {
+                changed |= self.links.entry(a).or_default().insert(b);
+                self.links.entry(b).or_default().insert(a);
+            }
+        }
+        changed
+    }
+
+    /// Returns true if no node has been learned yet
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the ids of the known nodes, sorted
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.keys().copied()
+    }
+
+    /// Returns the type of a known node
+    pub fn node_type(&self, id: NodeId) -> Option<NodeType> {
+        self.nodes.get(&id).copied()
+    }
+
+    /// Returns the known neighbours of a node
+    pub fn neighbours(&self, id: NodeId) -> BTreeSet<NodeId> {
+        self.links.get(&id).cloned().unwrap_or_default()
+    }
+
+    /// Returns every known link once, as `(a, b)` with `a < b`, sorted
+    pub fn links(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+        self.links
+            .iter()
+            .flat_map(|(a, links)| links.iter().map(move |b| (*a, *b)))
+            .filter(|(a, b)| a < b)
+    }
+
+    /// Returns true if a link between the two nodes has been seen
+    pub fn are_linked(&self, a: NodeId, b: NodeId) -> bool {
+        self.links.get(&a).is_some_and(|links| links.contains(&b))
+    }
+
+    /// Returns the shortest known route between two nodes, made only of drones
+    /// between the two ends
+    pub fn route(&self, from: NodeId, to: NodeId) -> Option<Vec<NodeId>> {
+        shortest_route(from, to, |node| self.neighbours(node), |node| self.node_type(node) == Some(NodeType::Drone))
+    }
+}
+
+/// Returns the shortest route between two nodes, found by a breadth first search.
+/// Only the nodes accepted by `is_transit` are crossed between the two ends,
+/// and the neighbours are visited in the order they are returned
+pub(crate) fn shortest_route<N, I>(from: NodeId, to: NodeId, neighbours: N, is_transit: impl Fn(NodeId) -> bool) -> Option<Vec<NodeId>>
+where
+    N: Fn(NodeId) -> I,
+    I: IntoIterator<Item = NodeId>,
+{
+    let mut previous = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            let mut route = vec![to];
+            while *route.last()? != from {
+                route.push(previous[route.last()?]);
+            }
+            route.reverse();
+            return Some(route);
+        }
+        if node != from && !is_transit(node) {
+            continue;
+        }
+        for neighbour in neighbours(node) {
+            if let Entry::Vacant(entry) = previous.entry(neighbour) {
+                entry.insert(node);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    None
+}
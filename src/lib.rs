@@ -1,3 +1,22 @@
 mod drone;
 mod test;
+pub mod simulation;
+pub mod conformance;
+pub mod network;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub use drone::RustDoIt;
+pub use drone::acl::{Acl, AclResponse, AclRule};
+pub use drone::command::RustDoItCommand;
+pub use drone::core::{Action, DroneCore};
+pub use drone::header::{validate_header, MalformedHeader};
+pub use drone::pdr_schedule::{PdrSchedule, PdrScheduleError};
+pub use drone::policy::{LoopPolicy, UnreachableAction, UnreachablePolicy};
+pub use drone::rate_limit::FloodRateLimit;
+pub use drone::report::DroneReport;
+pub use drone::stats::DroneStats;
+pub use drone::topology::Topology;
+#[cfg(feature = "async")]
+pub use drone::async_drone::AsyncRustDoIt;
+#[cfg(feature = "byzantine")]
+pub use drone::byzantine::ByzantineMode;
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use log::{debug, error, warn};
+use wg_2024::network::NodeId;
+use wg_2024::packet::NackType;
+use crate::drone::stats::DroneStats;
+use crate::network::Network;
+
+/// A counter exported for every drone: name, help and getter
+type Counter = (&'static str, &'static str, fn(&DroneStats) -> u64);
+
+const COUNTERS: [Counter; 9] = [
+    ("packets_forwarded", "Packets sent to a neighbour", DroneStats::packets_forwarded),
+    ("packets_dropped", "Fragments dropped", DroneStats::packets_dropped),
+    ("controller_shortcuts", "Packets sent to the controller", DroneStats::controller_shortcuts),
+    ("flood_requests", "Flood requests received", DroneStats::flood_requests),
+    ("routing_loops", "Packets received with a route going through a node twice", DroneStats::routing_loops),
+    ("route_repairs", "Routes repaired around a missing neighbour", DroneStats::route_repairs),
+    ("floods_throttled", "Flood requests answered because of the rate limit", DroneStats::floods_throttled),
+    ("packets_denied", "Packets denied by the access control list", DroneStats::packets_denied),
+    ("bits_flipped", "Bits flipped in the forwarded fragments", DroneStats::bits_flipped),
+];
+
+/// How long a connection may stay idle while its request is read or its response
+/// written, since the connections are answered one at a time
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The nack types exported, with the value of their `nack_type` label
+const NACK_TYPES: [(&str, NackType); 4] = [
+    ("error_in_routing", NackType::ErrorInRouting(0)),
+    ("destination_is_drone", NackType::DestinationIsDrone),
+    ("dropped", NackType::Dropped),
+    ("unexpected_recipient", NackType::UnexpectedRecipient(0)),
+];
+
+#[derive(Debug)]
+struct DroneMetrics {
+    implementation: String,
+    stats: Arc<DroneStats>,
+}
+
+/// The counters of a set of drones, rendered in the Prometheus text exposition format.
+///
+/// The counters are read when the metrics are rendered, so a `Metrics` built
+/// once keeps following the drones while they run. Cloning it shares the drones
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    drones: Arc<RwLock<BTreeMap<NodeId, DroneMetrics>>>,
+}
+
+impl Metrics {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follows the counters of every drone of the network
+    pub fn from_network(network: &Network) -> Self {
+        let metrics = Self::new();
+        for drone in network.drones() {
+            if let Some(stats) = network.stats(drone) {
+                metrics.register(drone, network.implementation(drone).unwrap_or_default(), stats);
+            }
+        }
+        metrics
+    }
+
+    /// Follows the counters of a drone, replacing the ones registered with the same id
+    pub fn register(&self, drone: NodeId, implementation: &str, stats: Arc<DroneStats>) {
+        let mut drones = self.drones.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        drones.insert(drone, DroneMetrics {
+            implementation: implementation.to_string(),
+            stats,
+        });
+    }
+
+    /// Renders the current value of the counters
+    pub fn render(&self) -> String {
+        let drones = self.drones.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut out = String::new();
+
+        for (name, help, counter) in COUNTERS.iter() {
+            writeln!(out, "# HELP rust_do_it_{}_total {}", name, help).ok();
+            writeln!(out, "# TYPE rust_do_it_{}_total counter", name).ok();
+            for (id, drone) in drones.iter() {
+                writeln!(
+                    out,
+                    "rust_do_it_{}_total{{drone=\"{}\",implementation=\"{}\"}} {}",
+                    name,
+                    id,
+                    escape(&drone.implementation),
+                    counter(&drone.stats)
+                ).ok();
+            }
+        }
+
+        writeln!(out, "# HELP rust_do_it_nacks_total Nacks generated, by type").ok();
+        writeln!(out, "# TYPE rust_do_it_nacks_total counter").ok();
+        for (id, drone) in drones.iter() {
+            for (label, nack_type) in NACK_TYPES.iter() {
+                writeln!(
+                    out,
+                    "rust_do_it_nacks_total{{drone=\"{}\",implementation=\"{}\",nack_type=\"{}\"}} {}",
+                    id,
+                    escape(&drone.implementation),
+                    label,
+                    drone.stats.nacks(*nack_type)
+                ).ok();
+            }
+        }
+        out
+    }
+
+    /// Serves the metrics over HTTP at `/metrics` on the given address,
+    /// in a thread of its own. Use port 0 to let the system choose a free port
+    pub fn serve(&self, addr: impl ToSocketAddrs) -> io::Result<MetricsServer> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let metrics = self.clone();
+        let stopped = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                match stream {
+                    Ok(stream) => if let Err(err) = metrics.respond(stream) {
+                        warn!("Metrics server could not answer: {}", err);
+                    },
+                    Err(err) => error!("Metrics server could not accept connection: {}", err),
+                }
+            }
+            debug!("Metrics server on {} stopped", addr);
+        });
+
+        Ok(MetricsServer { addr, stop, thread: Some(thread) })
+    }
+
+    fn respond(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+        stream.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+        let mut request_line = String::new();
+        let mut reader = BufReader::new(stream.try_clone()?);
+        reader.read_line(&mut request_line)?;
+        // skip the headers of the request
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 2 {
+            line.clear();
+        }
+
+        let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+        let (status, content_type, body) = match path {
+            "/metrics" => ("200 OK", "text/plain; version=0.0.4", self.render()),
+            _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+}
+
+/// A running HTTP endpoint serving the metrics, stopped when dropped
+#[derive(Debug)]
+pub struct MetricsServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    /// Returns the address the server listens on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stops the server and waits for its thread to end
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        self.stop.store(true, Ordering::Relaxed);
+        // wake up the thread blocked on `accept`
+        TcpStream::connect(self.addr).ok();
+        if thread.join().is_err() {
+            error!("Metrics server thread panicked");
+        }
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+use log::{info, warn};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::NodeId;
+use wg_2024::packet::NodeType;
+use super::shell::{ShellCommand, ShellError};
+use super::Network;
+
+/// A change made to the network by the chaos engine.
+///
+/// It is displayed with the syntax of the shell, so that a log of actions
+/// can be parsed back and replayed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChaosAction {
+    Crash(NodeId),
+    Link(NodeId, NodeId),
+    Unlink(NodeId, NodeId),
+    SetPdr(NodeId, f32),
+}
+
+impl fmt::Display for ChaosAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChaosAction::Crash(drone) => write!(f, "crash {}", drone),
+            ChaosAction::Link(a, b) => write!(f, "link {} {}", a, b),
+            ChaosAction::Unlink(a, b) => write!(f, "unlink {} {}", a, b),
+            ChaosAction::SetPdr(drone, pdr) => write!(f, "pdr {} {}", drone, pdr),
+        }
+    }
+}
+
+impl FromStr for ChaosAction {
+    type Err = ShellError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        match line.parse()? {
+            ShellCommand::Crash(drone) => Ok(ChaosAction::Crash(drone)),
+            ShellCommand::Link(a, b) => Ok(ChaosAction::Link(a, b)),
+            ShellCommand::Unlink(a, b) => Ok(ChaosAction::Unlink(a, b)),
+            ShellCommand::Pdr(drone, pdr) => Ok(ChaosAction::SetPdr(drone, pdr)),
+            _ => Err(ShellError::UnknownCommand(line.trim().to_string())),
+        }
+    }
+}
+
+impl ChaosAction {
+    /// Applies the action to the network through the commands of the protocol,
+    /// returning false if no drone accepted it
+    pub fn apply(&self, network: &mut Network) -> bool {
+        match *self {
+            ChaosAction::Crash(drone) => network.crash(drone),
+            ChaosAction::Link(a, b) => network.link(a, b),
+            ChaosAction::Unlink(a, b) => network.unlink(a, b),
+            ChaosAction::SetPdr(drone, pdr) => network.send_command(drone, DroneCommand::SetPacketDropRate(pdr)),
+        }
+    }
+}
+
+/// How often each kind of action is chosen, and the limits of the engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChaosConfig {
+    pub crash_weight: u32,
+    pub link_weight: u32,
+    pub unlink_weight: u32,
+    pub pdr_weight: u32,
+    /// The most drones the engine may crash, `None` for no limit
+    pub max_crashes: Option<usize>,
+    /// The highest packet drop rate the engine may set
+    pub max_pdr: f32,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            crash_weight: 1,
+            link_weight: 2,
+            unlink_weight: 2,
+            pdr_weight: 3,
+            max_crashes: None,
+            max_pdr: 0.5,
+        }
+    }
+}
+
+/// Why a chaos config was refused
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChaosConfigError {
+    /// The highest packet drop rate is not between 0 and 1
+    InvalidMaxPdr(f32),
+}
+
+impl fmt::Display for ChaosConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChaosConfigError::InvalidMaxPdr(pdr) => write!(f, "the highest packet drop rate {} is not between 0 and 1", pdr),
+        }
+    }
+}
+
+impl std::error::Error for ChaosConfigError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ActionKind {
+    Crash,
+    Link,
+    Unlink,
+    SetPdr,
+}
+
+/// Randomly changes a running network, to check that the protocols of the
+/// clients and the servers survive churn.
+///
+/// Every action keeps the running drones connected, and every client and server
+/// attached to at least one running drone. The same seed on the same network
+/// gives the same actions, which are also logged to be replayed
+#[derive(Debug)]
+pub struct ChaosEngine {
+    rng: StdRng,
+    config: ChaosConfig,
+    crashes: usize,
+    log: Vec<ChaosAction>,
+}
+
+impl ChaosEngine {
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            config: ChaosConfig::default(),
+            crashes: 0,
+            log: Vec::new(),
+        }
+    }
+
+    /// Replaces the default config, refusing a highest packet drop rate
+    /// the drones would not accept
+    pub fn with_config(mut self, config: ChaosConfig) -> Result<Self, ChaosConfigError> {
+        if !(0.0..=1.0).contains(&config.max_pdr) {
+            return Err(ChaosConfigError::InvalidMaxPdr(config.max_pdr));
+        }
+        self.config = config;
+        Ok(self)
+    }
+
+    /// Returns the actions applied so far, in order
+    pub fn log(&self) -> &[ChaosAction] {
+        &self.log
+    }
+
+    /// Applies a random action that keeps the constraints, returning it.
+    /// Returns `None` if no action is possible
+    pub fn step(&mut self, network: &mut Network) -> Option<ChaosAction> {
+        let graph = LiveGraph::of(network);
+
+        let mut kinds = vec![
+            (ActionKind::Crash, self.config.crash_weight),
+            (ActionKind::Link, self.config.link_weight),
+            (ActionKind::Unlink, self.config.unlink_weight),
+            (ActionKind::SetPdr, self.config.pdr_weight),
+        ];
+        kinds.retain(|(_, weight)| *weight > 0);
+
+        // the kinds are tried in a random order following their weights,
+        // until one of them has a candidate
+        while !kinds.is_empty() {
+            let index = kinds
+                .choose_weighted(&mut self.rng, |(_, weight)| *weight)
+                .ok()
+                .and_then(|chosen| kinds.iter().position(|kind| kind == chosen))?;
+            let (kind, _) = kinds.remove(index);
+
+            let candidates = self.candidates(kind, &graph);
+            let Some(action) = candidates.choose(&mut self.rng).copied() else {
+                continue;
+            };
+            let action = match action {
+                ChaosAction::SetPdr(drone, _) => {
+                    // rounded to be replayed exactly from the text of the log
+                    let pdr = self.rng.gen_range(0.0..=self.config.max_pdr);
+                    ChaosAction::SetPdr(drone, (pdr * 100.0).round() / 100.0)
+                },
+                action => action,
+            };
+
+            if !action.apply(network) {
+                warn!("Chaos: {} refused by the network", action);
+                continue;
+            }
+            if let ChaosAction::Crash(_) = action {
+                self.crashes += 1;
+            }
+            info!("Chaos: {}", action);
+            self.log.push(action);
+            return Some(action);
+        }
+        None
+    }
+
+    /// Applies up to `steps` random actions, returning those applied
+    pub fn run(&mut self, network: &mut Network, steps: usize) -> Vec<ChaosAction> {
+        (0..steps).map_while(|_| self.step(network)).collect()
+    }
+
+    fn candidates(&self, kind: ActionKind, graph: &LiveGraph) -> Vec<ChaosAction> {
+        let drones = graph.drones();
+        match kind {
+            ActionKind::Crash => {
+                if self.config.max_crashes.is_some_and(|max| self.crashes >= max) {
+                    return Vec::new();
+                }
+                drones
+                    .iter()
+                    .filter(|drone| graph.without_node(**drone).is_healthy())
+                    .map(|drone| ChaosAction::Crash(*drone))
+                    .collect()
+            },
+            ActionKind::Link => {
+                let mut candidates = Vec::new();
+                for a in &drones {
+                    for b in graph.links.keys() {
+                        let linked = graph.links[a].contains(b);
+                        let mirrored = a > b && graph.is_drone(*b);
+                        if a != b && !linked && !mirrored {
+                            candidates.push(ChaosAction::Link(*a, *b));
+                        }
+                    }
+                }
+                candidates
+            },
+            ActionKind::Unlink => graph
+                .edges()
+                .into_iter()
+                .filter(|(a, b)| graph.without_link(*a, *b).is_healthy())
+                .map(|(a, b)| ChaosAction::Unlink(a, b))
+                .collect(),
+            ActionKind::SetPdr => drones.iter().map(|drone| ChaosAction::SetPdr(*drone, 0.0)).collect(),
+        }
+    }
+}
+
+/// Applies the actions of a log to a network, in order
+pub fn replay(network: &mut Network, actions: &[ChaosAction]) {
+    for action in actions {
+        info!("Chaos replay: {}", action);
+        action.apply(network);
+    }
+}
+
+/// The links between the running nodes of a network
+#[derive(Debug, Clone)]
+struct LiveGraph {
+    node_types: BTreeMap<NodeId, NodeType>,
+    links: BTreeMap<NodeId, BTreeSet<NodeId>>,
+}
+
+impl LiveGraph {
+    fn of(network: &Network) -> Self {
+        let node_types = network
+            .nodes()
+            .filter(|id| !network.is_crashed(*id))
+            .filter_map(|id| network.node_type(id).map(|node_type| (id, node_type)))
+            .collect::<BTreeMap<_, _>>();
+        let links = node_types
+            .keys()
+            .map(|id| {
+                let neighbours = network
+                    .neighbours(*id)
+                    .into_iter()
+                    .filter(|neighbour| node_types.contains_key(neighbour))
+                    .collect();
+                (*id, neighbours)
+            })
+            .collect();
+        Self { node_types, links }
+    }
+
+    fn is_drone(&self, id: NodeId) -> bool {
+        self.node_types.get(&id) == Some(&NodeType::Drone)
+    }
+
+    fn drones(&self) -> Vec<NodeId> {
+        self.links.keys().copied().filter(|id| self.is_drone(*id)).collect()
+    }
+
+    fn edges(&self) -> Vec<(NodeId, NodeId)> {
+        self.links
+            .iter()
+            .flat_map(|(a, neighbours)| neighbours.iter().map(move |b| (*a, *b)))
+            .filter(|(a, b)| a < b)
+            .collect()
+    }
+
+    fn without_node(&self, id: NodeId) -> Self {
+        let mut graph = self.clone();
+        graph.node_types.remove(&id);
+        graph.links.remove(&id);
+        for neighbours in graph.links.values_mut() {
+            neighbours.remove(&id);
+        }
+        graph
+    }
+
+    fn without_link(&self, a: NodeId, b: NodeId) -> Self {
+        let mut graph = self.clone();
+        for (from, to) in [(a, b), (b, a)] {
+            if let Some(neighbours) = graph.links.get_mut(&from) {
+                neighbours.remove(&to);
+            }
+        }
+        graph
+    }
+
+    fn is_healthy(&self) -> bool {
+        // The drones must be connected among themselves, since the endpoints
+        // do not forward packets, and every endpoint must reach one of them
+        // ### Parameters:
+        // - `self`: The graph to be checked
+
+        let drones = self.drones();
+        let Some(first) = drones.first() else {
+            return self.links.is_empty();
+        };
+
+        let mut reached = BTreeSet::from([*first]);
+        let mut stack = vec![*first];
+        while let Some(drone) = stack.pop() {
+            for neighbour in &self.links[&drone] {
+                if self.is_drone(*neighbour) && reached.insert(*neighbour) {
+                    stack.push(*neighbour);
+                }
+            }
+        }
+
+        reached.len() == drones.len()
+            && self.links
+                .iter()
+                .filter(|(id, _)| !self.is_drone(**id))
+                .all(|(_, neighbours)| neighbours.iter().any(|neighbour| self.is_drone(*neighbour)))
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// Size of the checksum appended to a sealed message
+pub const CHECKSUM_SIZE: usize = 4;
+
+/// Why a sealed message could not be opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumError {
+    /// The message is shorter than its checksum
+    TooShort,
+    /// The checksum carried by the message does not match its content
+    Mismatch { expected: u32, actual: u32 },
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChecksumError::TooShort => write!(f, "the message is too short to carry a checksum"),
+            ChecksumError::Mismatch { expected, actual } => {
+                write!(f, "checksum {:08x} does not match the content, whose checksum is {:08x}", expected, actual)
+            },
+        }
+    }
+}
+
+impl std::error::Error for ChecksumError {}
+
+/// Returns the CRC-32 (IEEE 802.3) of the data
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Appends the checksum of a message to it, so that it is carried by the
+/// payload of its last fragments
+pub fn seal(data: &[u8]) -> Vec<u8> {
+    let mut sealed = Vec::with_capacity(data.len() + CHECKSUM_SIZE);
+    sealed.extend_from_slice(data);
+    sealed.extend_from_slice(&crc32(data).to_be_bytes());
+    sealed
+}
+
+/// Verifies the checksum of a sealed message, returning the message without it
+pub fn open(sealed: &[u8]) -> Result<&[u8], ChecksumError> {
+    let Some(split) = sealed.len().checked_sub(CHECKSUM_SIZE) else {
+        return Err(ChecksumError::TooShort);
+    };
+    let (data, checksum) = sealed.split_at(split);
+    let expected = u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+    let actual = crc32(data);
+    if expected == actual {
+        Ok(data)
+    } else {
+        Err(ChecksumError::Mismatch { expected, actual })
+    }
+}
@@ -0,0 +1,77 @@
+use serde::Deserialize;
+use wg_2024::config::{Client, Config, Drone, Server};
+use wg_2024::network::NodeId;
+
+/// Name of the implementation used for the drones that do not name one
+pub const DEFAULT_IMPLEMENTATION: &str = "rust_do_it";
+
+/// A drone entry of the topology, which can name the implementation to use
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DroneEntry {
+    pub id: NodeId,
+    pub connected_node_ids: Vec<NodeId>,
+    pub pdr: f32,
+    #[serde(default)]
+    pub implementation: Option<String>,
+}
+
+impl DroneEntry {
+    /// Returns the name of the implementation of the drone
+    pub fn implementation(&self) -> &str {
+        self.implementation.as_deref().unwrap_or(DEFAULT_IMPLEMENTATION)
+    }
+}
+
+/// A topology in the format of `config.toml`, where every drone entry
+/// can have an `implementation = "<name>"` key
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NetworkConfig {
+    #[serde(default)]
+    pub drone: Vec<DroneEntry>,
+    #[serde(default)]
+    pub client: Vec<Client>,
+    #[serde(default)]
+    pub server: Vec<Server>,
+}
+
+impl NetworkConfig {
+    /// Parses a topology from its TOML representation
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+}
+
+impl From<Config> for NetworkConfig {
+    fn from(config: Config) -> Self {
+        Self {
+            drone: config.drone
+                .into_iter()
+                .map(|drone| DroneEntry {
+                    id: drone.id,
+                    connected_node_ids: drone.connected_node_ids,
+                    pdr: drone.pdr,
+                    implementation: None,
+                })
+                .collect(),
+            client: config.client,
+            server: config.server,
+        }
+    }
+}
+
+impl From<&NetworkConfig> for Config {
+    fn from(config: &NetworkConfig) -> Self {
+        Config {
+            drone: config.drone
+                .iter()
+                .map(|drone| Drone {
+                    id: drone.id,
+                    connected_node_ids: drone.connected_node_ids.clone(),
+                    pdr: drone.pdr,
+                })
+                .collect(),
+            client: config.client.clone(),
+            server: config.server.clone(),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::NodeType;
+use crate::drone::topology::Topology;
+use super::{Network, NetworkConfig};
+
+#[derive(Debug, Clone, PartialEq)]
+struct DotNode {
+    node_type: NodeType,
+    pdr: Option<f32>,
+    crashed: bool,
+}
+
+/// A topology rendered in the Graphviz DOT language, as an undirected graph.
+///
+/// Nodes are coloured by `NodeType` and drones are labelled with their packet
+/// drop rate when it is known. A route can be overlaid with `with_path`, and
+/// the number of packets sent over each link with `with_traffic` or `with_events`.
+/// The output is sorted by id, so the same topology always gives the same text
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DotGraph {
+    nodes: BTreeMap<NodeId, DotNode>,
+    links: BTreeSet<(NodeId, NodeId)>,
+    path: Vec<NodeId>,
+    traffic: BTreeMap<(NodeId, NodeId), u64>,
+}
+
+impl DotGraph {
+
+    /// Builds the graph of the topology described by a config
+    pub fn from_config(config: &NetworkConfig) -> Self {
+        let mut graph = Self::default();
+        for drone in config.drone.iter() {
+            graph.add_node(drone.id, NodeType::Drone, Some(drone.pdr), false);
+            graph.add_links(drone.id, drone.connected_node_ids.iter());
+        }
+        for client in config.client.iter() {
+            graph.add_node(client.id, NodeType::Client, None, false);
+            graph.add_links(client.id, client.connected_drone_ids.iter());
+        }
+        for server in config.server.iter() {
+            graph.add_node(server.id, NodeType::Server, None, false);
+            graph.add_links(server.id, server.connected_drone_ids.iter());
+        }
+        graph
+    }
+
+    /// Builds the graph of a running network, with the current neighbours
+    /// and packet drop rates of its drones. Crashed drones are greyed out
+    pub fn from_network(network: &Network) -> Self {
+        let mut graph = Self::default();
+        for id in network.nodes() {
+            let Some(node_type) = network.node_type(id) else {
+                continue;
+            };
+            graph.add_node(id, node_type, network.pdr(id), network.is_crashed(id));
+            graph.add_links(id, network.neighbours(id).iter());
+        }
+        graph
+    }
+
+    /// Builds the graph of a topology learned by a drone
+    pub fn from_topology(topology: &Topology) -> Self {
+        let mut graph = Self::default();
+        for id in topology.nodes() {
+            if let Some(node_type) = topology.node_type(id) {
+                graph.add_node(id, node_type, None, false);
+            }
+        }
+        graph.links.extend(topology.links());
+        graph
+    }
+
+    /// Highlights the route of a source routing header. Hops that are not
+    /// linked in the topology are drawn as dashed edges
+    pub fn with_path(mut self, srh: &SourceRoutingHeader) -> Self {
+        self.path = srh.hops.clone();
+        self
+    }
+
+    /// Adds a number of packets sent over each link, the two ends of a link
+    /// can be given in any order
+    pub fn with_traffic(mut self, traffic: impl IntoIterator<Item = ((NodeId, NodeId), u64)>) -> Self {
+        for ((a, b), count) in traffic {
+            *self.traffic.entry(edge(a, b)).or_default() += count;
+        }
+        self
+    }
+
+    /// Counts the `PacketSent` events as traffic over the link between
+    /// the sender and the receiver of the packet
+    pub fn with_events<'a>(self, events: impl IntoIterator<Item = &'a DroneEvent>) -> Self {
+        let traffic = events
+            .into_iter()
+            .filter_map(|event| match event {
+                DroneEvent::PacketSent(packet) => {
+                    let srh = &packet.routing_header;
+                    let to = srh.hops.get(srh.hop_index)?;
+                    let from = srh.hops.get(srh.hop_index.checked_sub(1)?)?;
+                    Some(((*from, *to), 1))
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        self.with_traffic(traffic)
+    }
+
+    /// Renders the graph in the DOT language
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+
+    fn add_node(&mut self, id: NodeId, node_type: NodeType, pdr: Option<f32>, crashed: bool) {
+        self.nodes.insert(id, DotNode { node_type, pdr, crashed });
+    }
+
+    fn add_links<'a>(&mut self, id: NodeId, neighbours: impl Iterator<Item = &'a NodeId>) {
+        for neighbour in neighbours {
+            if *neighbour != id {
+                self.links.insert(edge(id, *neighbour));
+            }
+        }
+    }
+
+    fn path_edges(&self) -> BTreeSet<(NodeId, NodeId)> {
+        self.path
+            .windows(2)
+            .filter(|pair| pair[0] != pair[1])
+            .map(|pair| edge(pair[0], pair[1]))
+            .collect()
+    }
+}
+
+impl fmt::Display for DotGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path_edges = self.path_edges();
+
+        writeln!(f, "graph network {{")?;
+        writeln!(f, "    node [style=filled];")?;
+
+        for (id, node) in self.nodes.iter() {
+            let (shape, colour, kind) = match node.node_type {
+                NodeType::Drone => ("ellipse", "lightblue", "drone"),
+                NodeType::Client => ("box", "palegreen", "client"),
+                NodeType::Server => ("box", "orange", "server"),
+            };
+            let label = match (node.crashed, node.pdr) {
+                (true, _) => format!("{}\\ncrashed", id),
+                (false, Some(pdr)) => format!("{}\\npdr {:.2}", id, pdr),
+                (false, None) => format!("{}\\n{}", id, kind),
+            };
+            let colour = if node.crashed { "grey" } else { colour };
+            write!(f, "    {} [label=\"{}\", shape={}, fillcolor={}", id, label, shape, colour)?;
+            if self.path.contains(id) {
+                write!(f, ", color=red, penwidth=2")?;
+            }
+            writeln!(f, "];")?;
+        }
+
+        let edges = self.links.iter().chain(path_edges.iter()).collect::<BTreeSet<_>>();
+        for (a, b) in edges {
+            let mut attributes = Vec::new();
+            if let Some(count) = self.traffic.get(&(*a, *b)) {
+                attributes.push(format!("label=\"{}\"", count));
+            }
+            if path_edges.contains(&(*a, *b)) {
+                attributes.push("color=red".to_string());
+                attributes.push("penwidth=2".to_string());
+                if !self.links.contains(&(*a, *b)) {
+                    attributes.push("style=dashed".to_string());
+                }
+            }
+
+            if attributes.is_empty() {
+                writeln!(f, "    {} -- {};", a, b)?;
+            } else {
+                writeln!(f, "    {} -- {} [{}];", a, b, attributes.join(", "))?;
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+fn edge(a: NodeId, b: NodeId) -> (NodeId, NodeId) {
+    (a.min(b), a.max(b))
+}
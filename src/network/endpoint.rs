@@ -0,0 +1,432 @@
+use std::collections::{BTreeMap, HashMap};
+use crossbeam_channel::Receiver;
+use log::{debug, warn};
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{FloodRequest, FloodResponse, Fragment, NackType, NodeType, Packet, PacketType};
+use super::checksum;
+use super::Network;
+
+/// Size of the payload of a fragment
+pub const FRAGMENT_SIZE: usize = 128;
+
+/// Most fragments a received message may be made of, larger messages are refused
+/// before any buffer is allocated for them
+pub const MAX_FRAGMENTS: u64 = 1 << 16;
+
+/// Most sealed messages kept for retransmission, the oldest ones are forgotten first
+pub const MAX_OUTGOING: usize = 1024;
+
+/// A message reassembled by an endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub session_id: u64,
+    pub data: Vec<u8>,
+}
+
+/// The fragments of a message received so far, by index
+type PartialMessage = Vec<Option<Vec<u8>>>;
+
+/// A sealed message kept by its source until it is delivered intact
+#[derive(Debug, Clone)]
+struct Outgoing {
+    to: NodeId,
+    data: Vec<u8>,
+    retransmissions: u32,
+}
+
+/// Something that happened at a client or a server of the network
+#[derive(Debug, Clone, PartialEq)]
+pub enum EndpointEvent {
+    /// Every fragment of a message reached its destination
+    Delivered(Message),
+    /// The destination of a message acknowledged one of its fragments
+    Acked { node: NodeId, session_id: u64, fragment_index: u64 },
+    /// A drone refused one of the fragments sent by the node
+    Nacked { node: NodeId, session_id: u64, fragment_index: u64, nack_type: NackType },
+    /// A flood started by the node reached the end of a path
+    FloodResponse { node: NodeId, flood_id: u64, path_trace: Vec<(NodeId, NodeType)> },
+    /// A message reached its destination with a wrong checksum, and was sent
+    /// again unless it ran out of retransmissions
+    Corrupted { from: NodeId, to: NodeId, session_id: u64, retransmitted: bool },
+}
+
+/// The counters of the traffic of all the endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EndpointStats {
+    pub messages_sent: u64,
+    pub messages_delivered: u64,
+    /// Messages that could not be sent because no route reaches their destination
+    pub messages_unroutable: u64,
+    pub fragments_sent: u64,
+    /// Fragments that reached their destination, counted when their message is
+    /// verified if messages are sealed
+    pub fragments_delivered: u64,
+    pub acks_received: u64,
+    pub nacks_received: u64,
+    pub floods_started: u64,
+    /// Messages reassembled with a wrong checksum, once for each attempt
+    pub messages_corrupted: u64,
+    pub retransmissions: u64,
+    /// Fragments refused because of their index or number of fragments
+    pub fragments_rejected: u64,
+}
+
+impl EndpointStats {
+    /// Returns the ratio between the fragments that reached their destination
+    /// and the fragments sent
+    pub fn delivery_ratio(&self) -> f64 {
+        if self.fragments_sent == 0 {
+            0.0
+        } else {
+            self.fragments_delivered as f64 / self.fragments_sent as f64
+        }
+    }
+}
+
+/// The clients and servers of a running network, acting as simple endpoints.
+///
+/// Messages are split in fragments and sent along the shortest route known to the
+/// network. Every endpoint acknowledges the fragments it receives, answers the flood
+/// requests and reassembles the messages. Nothing happens in the background:
+/// the packets waiting at the endpoints are handled by `poll`.
+///
+/// With `with_checksums` every message carries a checksum, verified by its
+/// destination. A corrupted message is sent again by its source, as if the
+/// destination asked for it
+#[derive(Debug)]
+pub struct Endpoints {
+    receivers: BTreeMap<NodeId, Receiver<Packet>>,
+    partial: HashMap<(NodeId, NodeId, u64), PartialMessage>,  // Fragments received, by destination, source and session
+    next_session_id: u64,
+    next_flood_id: u64,
+    stats: EndpointStats,
+    checksums: Option<u32>,                                   // Retransmissions allowed for a corrupted message, if messages are sealed
+    outgoing: HashMap<(NodeId, u64), Outgoing>,               // Sealed messages not delivered yet, by source and session
+}
+
+impl Endpoints {
+
+    /// Takes the role of every client and server of the network
+    pub fn new(network: &Network) -> Self {
+        Self {
+            receivers: network.clients()
+                .chain(network.servers())
+                .filter_map(|id| Some((id, network.receiver(id)?)))
+                .collect(),
+            partial: HashMap::new(),
+            next_session_id: 0,
+            next_flood_id: 0,
+            stats: EndpointStats::default(),
+            checksums: None,
+            outgoing: HashMap::new(),
+        }
+    }
+
+    /// Seals every message sent with a checksum, see `checksum::seal`. A message
+    /// reassembled with a wrong checksum is sent again, up to `max_retransmissions` times
+    pub fn with_checksums(mut self, max_retransmissions: u32) -> Self {
+        self.checksums = Some(max_retransmissions);
+        self
+    }
+
+    /// Returns the counters of the traffic
+    pub fn stats(&self) -> EndpointStats {
+        self.stats
+    }
+
+    /// Returns the number of sealed messages not delivered intact yet, kept to be sent
+    /// again if they are corrupted. At most `MAX_OUTGOING` messages are kept
+    pub fn pending(&self) -> usize {
+        self.outgoing.len()
+    }
+
+    /// Sends a message from an endpoint to another one, along the shortest route.
+    /// Returns the session id of the message, None if there is no route
+    pub fn send(&mut self, network: &Network, from: NodeId, to: NodeId, data: &[u8]) -> Option<u64> {
+        if !self.receivers.contains_key(&from) {
+            warn!("Node {} is not an endpoint of the network", from);
+            return None;
+        }
+        let Some(route) = network.route(from, to).filter(|route| route.len() > 2) else {
+            warn!("No route from {} to {}", from, to);
+            self.stats.messages_unroutable += 1;
+            return None;
+        };
+
+        self.next_session_id += 1;
+        let session_id = self.next_session_id;
+        if self.checksums.is_some() {
+            let data = checksum::seal(data);
+            self.send_fragments(network, &route, session_id, &data);
+            self.outgoing.insert((from, session_id), Outgoing { to, data, retransmissions: 0 });
+            if self.outgoing.len() > MAX_OUTGOING {
+                self.forget_oldest();
+            }
+        } else {
+            self.send_fragments(network, &route, session_id, data);
+        }
+        self.stats.messages_sent += 1;
+        Some(session_id)
+    }
+
+    fn send_fragments(&mut self, network: &Network, route: &[NodeId], session_id: u64, data: &[u8]) {
+        // This function splits a message in fragments and sends them along the route
+        // ### Parameters:
+        // - `network`: The network to send the fragments through
+        // - `route`: The route of the fragments, from the source to the destination
+        // - `session_id`: The session id of the message
+        // - `data`: The content of the message
+
+        let chunks = data.chunks(FRAGMENT_SIZE).collect::<Vec<_>>();
+        // an empty message still needs a fragment to be delivered
+        let chunks = if chunks.is_empty() { vec![&[][..]] } else { chunks };
+
+        for (fragment_index, chunk) in chunks.iter().enumerate() {
+            let mut payload = [0; FRAGMENT_SIZE];
+            payload[..chunk.len()].copy_from_slice(chunk);
+            let packet = Packet::new_fragment(
+                SourceRoutingHeader::new(route.to_vec(), 1),
+                session_id,
+                Fragment {
+                    fragment_index: fragment_index as u64,
+                    total_n_fragments: chunks.len() as u64,
+                    length: chunk.len() as u8,
+                    data: payload,
+                },
+            );
+            if network.send(route[1], packet) {
+                self.stats.fragments_sent += 1;
+            }
+        }
+    }
+
+    fn retransmit(&mut self, network: &Network, from: NodeId, session_id: u64) -> bool {
+        // This function sends a sealed message again from its source, along the
+        // shortest route, if it has retransmissions left
+        // ### Parameters:
+        // - `network`: The network to send the fragments through
+        // - `from`: The source of the message
+        // - `session_id`: The session id of the message
+        //
+        // ### Returns:
+        // - `bool`: True if the message was sent again
+
+        let max_retransmissions = self.checksums.unwrap_or_default();
+        let Some(outgoing) = self.outgoing.get_mut(&(from, session_id)) else {
+            return false;
+        };
+        if outgoing.retransmissions >= max_retransmissions {
+            warn!("Message {} from {} is still corrupted after {} retransmissions", session_id, from, max_retransmissions);
+            self.outgoing.remove(&(from, session_id));
+            return false;
+        }
+        outgoing.retransmissions += 1;
+        let (to, data) = (outgoing.to, outgoing.data.clone());
+
+        let Some(route) = network.route(from, to).filter(|route| route.len() > 2) else {
+            warn!("No route from {} to {} to send message {} again", from, to, session_id);
+            self.outgoing.remove(&(from, session_id));
+            return false;
+        };
+        self.send_fragments(network, &route, session_id, &data);
+        self.stats.retransmissions += 1;
+        true
+    }
+
+    fn forget_oldest(&mut self) {
+        // This function forgets the sealed message sent first, which can no longer
+        // be sent again. Messages whose fragments were lost are never delivered,
+        // so they would otherwise be kept forever
+
+        let oldest = self.outgoing.keys().min_by_key(|(_, session_id)| *session_id).copied();
+        if let Some((from, session_id)) = oldest {
+            debug!("Forgetting message {} from {}, too many messages waiting for delivery", session_id, from);
+            self.outgoing.remove(&(from, session_id));
+        }
+    }
+
+    /// Starts a flood from an endpoint. Returns the id of the flood
+    pub fn flood(&mut self, network: &Network, from: NodeId) -> Option<u64> {
+        let node_type = network.node_type(from).filter(|_| self.receivers.contains_key(&from))?;
+        self.next_session_id += 1;
+        self.next_flood_id += 1;
+
+        for neighbour in network.neighbours(from) {
+            let packet = Packet::new_flood_request(
+                SourceRoutingHeader::new(vec![], 0),
+                self.next_session_id,
+                FloodRequest::initialize(self.next_flood_id, from, node_type),
+            );
+            network.send(neighbour, packet);
+        }
+        self.stats.floods_started += 1;
+        Some(self.next_flood_id)
+    }
+
+    /// Delivers the packets that the drones sent to the controller to their destination,
+    /// as the simulation controller does
+    pub fn handle_events(&self, network: &Network, events: &[(NodeId, DroneEvent)]) {
+        for (_, event) in events.iter() {
+            if let DroneEvent::ControllerShortcut(packet) = event {
+                match packet.routing_header.hops.last() {
+                    Some(destination) => {
+                        network.send(*destination, packet.clone());
+                    },
+                    None => warn!("Controller shortcut without destination {:?}", packet),
+                }
+            }
+        }
+    }
+
+    /// Handles the packets waiting at the endpoints
+    pub fn poll(&mut self, network: &Network) -> Vec<EndpointEvent> {
+        let mut events = Vec::new();
+        let packets = self.receivers
+            .iter()
+            .flat_map(|(id, receiver)| receiver.try_iter().map(move |packet| (*id, packet)))
+            .collect::<Vec<_>>();
+
+        for (node, packet) in packets {
+            debug!("Endpoint {} received packet {:?}", node, packet);
+            match &packet.pack_type {
+                PacketType::MsgFragment(fragment) => {
+                    self.handle_fragment(network, node, &packet, fragment, &mut events);
+                },
+                PacketType::Ack(ack) => {
+                    self.stats.acks_received += 1;
+                    events.push(EndpointEvent::Acked {
+                        node,
+                        session_id: packet.session_id,
+                        fragment_index: ack.fragment_index,
+                    });
+                },
+                PacketType::Nack(nack) => {
+                    self.stats.nacks_received += 1;
+                    events.push(EndpointEvent::Nacked {
+                        node,
+                        session_id: packet.session_id,
+                        fragment_index: nack.fragment_index,
+                        nack_type: nack.nack_type,
+                    });
+                },
+                PacketType::FloodRequest(flood_request) => {
+                    Self::answer_flood(network, node, packet.session_id, flood_request.clone());
+                },
+                PacketType::FloodResponse(flood_response) => {
+                    events.push(EndpointEvent::FloodResponse {
+                        node,
+                        flood_id: flood_response.flood_id,
+                        path_trace: flood_response.path_trace.clone(),
+                    });
+                },
+            }
+        }
+        events
+    }
+
+    fn handle_fragment(
+        &mut self,
+        network: &Network,
+        node: NodeId,
+        packet: &Packet,
+        fragment: &Fragment,
+        events: &mut Vec<EndpointEvent>,
+    ) {
+        // This function acknowledges a fragment and reassembles its message.
+        // Fragments with an impossible index or number of fragments, or disagreeing
+        // with the fragments already received for the message, are refused
+        // ### Parameters:
+        // - `network`: The network to send the ack through
+        // - `node`: The endpoint that received the fragment
+        // - `packet`: The packet carrying the fragment
+        // - `fragment`: The fragment received
+        // - `events`: The events to which the delivered message is added
+
+        let srh = &packet.routing_header;
+        let from = srh.hops.first().copied().unwrap_or_default();
+        let key = (node, from, packet.session_id);
+        let total = fragment.total_n_fragments;
+        let expected = self.partial.get(&key).map_or(total, |fragments| fragments.len() as u64);
+        if total == 0 || total > MAX_FRAGMENTS || total != expected || fragment.fragment_index >= total {
+            warn!(
+                "Endpoint {} refused fragment {} of {} of message {} from {}, expecting {} fragments",
+                node, fragment.fragment_index, total, packet.session_id, from, expected
+            );
+            self.stats.fragments_rejected += 1;
+            return;
+        }
+
+        let travelled = srh.hops.get(..=srh.hop_index).unwrap_or(&srh.hops);
+        let route = travelled.iter().rev().copied().collect::<Vec<_>>();
+        if let Some(next_hop) = route.get(1) {
+            let ack = Packet::new_ack(SourceRoutingHeader::new(route.clone(), 1), packet.session_id, fragment.fragment_index);
+            network.send(*next_hop, ack);
+        }
+
+        let fragments = self.partial.entry(key).or_insert_with(|| vec![None; total as usize]);
+        let slot = &mut fragments[fragment.fragment_index as usize];
+        // a duplicate fragment is acknowledged again but delivered once, the fragments
+        // of a sealed message are only delivered once its checksum is verified
+        if slot.is_none() && self.checksums.is_none() {
+            self.stats.fragments_delivered += 1;
+        }
+        *slot = Some(fragment.data[..(fragment.length as usize).min(FRAGMENT_SIZE)].to_vec());
+
+        if fragments.iter().all(Option::is_some) {
+            let mut data = self.partial.remove(&key).unwrap_or_default().into_iter().flatten().flatten().collect::<Vec<_>>();
+            if self.checksums.is_some() {
+                match checksum::open(&data) {
+                    Ok(content) => {
+                        data.truncate(content.len());
+                        self.stats.fragments_delivered += total;
+                        self.outgoing.remove(&(from, packet.session_id));
+                    },
+                    Err(err) => {
+                        warn!("Endpoint {} received corrupted message {} from {}: {}", node, packet.session_id, from, err);
+                        self.stats.messages_corrupted += 1;
+                        let retransmitted = self.retransmit(network, from, packet.session_id);
+                        events.push(EndpointEvent::Corrupted {
+                            from,
+                            to: node,
+                            session_id: packet.session_id,
+                            retransmitted,
+                        });
+                        return;
+                    },
+                }
+            }
+            self.stats.messages_delivered += 1;
+            events.push(EndpointEvent::Delivered(Message {
+                from,
+                to: node,
+                session_id: packet.session_id,
+                data,
+            }));
+        }
+    }
+
+    fn answer_flood(network: &Network, node: NodeId, session_id: u64, mut flood_request: FloodRequest) {
+        if let Some(node_type) = network.node_type(node) {
+            flood_request.increment(node, node_type);
+        }
+        let mut route = flood_request.path_trace.iter().map(|(id, _)| *id).rev().collect::<Vec<_>>();
+        if route.last() != Some(&flood_request.initiator_id) {
+            route.push(flood_request.initiator_id);
+        }
+
+        if let Some(next_hop) = route.get(1) {
+            let response = Packet::new_flood_response(
+                SourceRoutingHeader::new(route.clone(), 1),
+                session_id,
+                FloodResponse {
+                    flood_id: flood_request.flood_id,
+                    path_trace: flood_request.path_trace,
+                },
+            );
+            network.send(*next_hop, response);
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::{Duration, Instant};
+use crossbeam_channel::Select;
+use wg_2024::controller::DroneEvent;
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, NackType, Packet, PacketType};
+use super::Network;
+
+/// How many standard deviations the observed drops can be away from the PDR
+const TOLERANCE: f64 = 3.0;
+
+/// A message of `fragments` fragments sent from a client to a server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Traffic {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub fragments: u64,
+}
+
+/// What the drones of one implementation did with the scripted traffic
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImplementationReport {
+    pub implementation: String,
+    pub drones: Vec<NodeId>,
+    /// Fragments that reached a drone of the implementation
+    pub handled: u64,
+    /// Fragments the drones reported as dropped
+    pub dropped: u64,
+    /// Drops expected from the PDR of the drones
+    pub expected_drops: f64,
+    /// Variance of the number of drops expected from the PDR of the drones
+    pub drops_variance: f64,
+    /// Fragments that vanished after reaching a drone, without any event or nack
+    pub lost: u64,
+    /// Nacks generated by the drones that should not happen on a healthy topology
+    pub unexpected_nacks: Vec<(NodeId, NackType)>,
+}
+
+impl ImplementationReport {
+    /// Returns the ratio between dropped and handled fragments
+    pub fn observed_drop_rate(&self) -> f64 {
+        if self.handled == 0 {
+            0.0
+        } else {
+            self.dropped as f64 / self.handled as f64
+        }
+    }
+
+    /// Returns true if the number of drops is compatible with the PDR of the drones
+    pub fn drop_rate_consistent(&self) -> bool {
+        (self.dropped as f64 - self.expected_drops).abs() <= TOLERANCE * self.drops_variance.sqrt() + 1.0
+    }
+
+    /// Returns true if the implementation behaved in a way that deserves a look
+    pub fn is_suspicious(&self) -> bool {
+        !self.unexpected_nacks.is_empty() || self.lost > 0 || !self.drop_rate_consistent()
+    }
+}
+
+/// The outcome of the scripted traffic, by implementation
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InteropReport {
+    pub sent: u64,
+    pub delivered: u64,
+    pub unroutable: Vec<Traffic>,
+    pub implementations: Vec<ImplementationReport>,
+}
+
+impl InteropReport {
+    /// Returns the report of an implementation
+    pub fn implementation(&self, name: &str) -> Option<&ImplementationReport> {
+        self.implementations.iter().find(|report| report.implementation == name)
+    }
+
+    /// Returns the names of the implementations that behaved unexpectedly
+    pub fn suspicious(&self) -> Vec<&str> {
+        self.implementations
+            .iter()
+            .filter(|report| report.is_suspicious())
+            .map(|report| report.implementation.as_str())
+            .collect()
+    }
+}
+
+impl fmt::Display for InteropReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "delivered {}/{} fragments", self.delivered, self.sent)?;
+        writeln!(
+            f,
+            "{:<16} {:>8} {:>8} {:>10} {:>8} {:>6} {:>6}",
+            "implementation", "handled", "dropped", "expected", "rate", "lost", "nacks"
+        )?;
+        for report in self.implementations.iter() {
+            writeln!(
+                f,
+                "{:<16} {:>8} {:>8} {:>10.1} {:>8.3} {:>6} {:>6}{}",
+                report.implementation,
+                report.handled,
+                report.dropped,
+                report.expected_drops,
+                report.observed_drop_rate(),
+                report.lost,
+                report.unexpected_nacks.len(),
+                if report.is_suspicious() { "  <- suspicious" } else { "" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Sends the scripted traffic through the network, along the shortest routes,
+/// and reports the behaviour of every drone implementation.
+///
+/// The harness waits until every fragment reached its server or a nack reached
+/// its client, and every drone reported the fragments sent to it, or until the
+/// timeout expires. The fragments still missing are blamed on the drone that
+/// received them last.
+pub fn run(network: &Network, traffic: &[Traffic], timeout: Duration) -> InteropReport {
+    let mut report = InteropReport::default();
+    let mut pending: HashMap<(u64, u64), Vec<NodeId>> = HashMap::new();
+    // fragments sent to every drone minus the ones it reported as sent or dropped
+    let mut unreported: HashMap<NodeId, i64> = HashMap::new();
+    let mut session_id = 0;
+
+    for message in traffic.iter() {
+        let Some(route) = network.route(message.from, message.to).filter(|route| route.len() > 2) else {
+            report.unroutable.push(*message);
+            continue;
+        };
+
+        session_id += 1;
+        for fragment_index in 0..message.fragments {
+            let packet = Packet::new_fragment(
+                SourceRoutingHeader::new(route.clone(), 1),
+                session_id,
+                Fragment {
+                    fragment_index,
+                    total_n_fragments: message.fragments,
+                    length: 128,
+                    data: [0; 128],
+                },
+            );
+            if network.send(route[1], packet) {
+                *unreported.entry(route[1]).or_default() += 1;
+                pending.insert((session_id, fragment_index), route.clone());
+                report.sent += 1;
+            }
+        }
+    }
+
+    let endpoints = network.clients()
+        .chain(network.servers())
+        .filter_map(|id| network.receiver(id))
+        .collect::<Vec<_>>();
+    let drones = network.drones
+        .iter()
+        .map(|(id, handle)| (*id, handle.event_recv.clone()))
+        .collect::<Vec<_>>();
+    let mut select = Select::new();
+    for receiver in endpoints.iter() {
+        select.recv(receiver);
+    }
+    for (_, receiver) in drones.iter() {
+        select.recv(receiver);
+    }
+
+    let mut events = Vec::new();
+    let mut unexpected_nacks = Vec::new();
+    let deadline = Instant::now() + timeout;
+
+    while !pending.is_empty() || unreported.values().any(|count| *count > 0) {
+        let Ok(operation) = select.select_deadline(deadline) else {
+            break;
+        };
+        let index = operation.index();
+        if let Some(receiver) = endpoints.get(index) {
+            let Ok(packet) = operation.recv(receiver) else {
+                select.remove(index);
+                continue;
+            };
+            let key = (packet.session_id, packet.get_fragment_index());
+            match packet.pack_type {
+                PacketType::MsgFragment(_) if pending.remove(&key).is_some() => {
+                    report.delivered += 1;
+                },
+                PacketType::Nack(nack) => {
+                    // the nacks carry the index of the fragment they refer to
+                    pending.remove(&(packet.session_id, nack.fragment_index));
+                    if nack.nack_type != NackType::Dropped {
+                        let origin = packet.routing_header.hops.first().copied().unwrap_or_default();
+                        unexpected_nacks.push((origin, nack.nack_type));
+                    }
+                },
+                _ => {}
+            }
+        } else {
+            let (drone, receiver) = &drones[index - endpoints.len()];
+            // a crashed drone closes its event channel
+            let Ok(event) = operation.recv(receiver) else {
+                select.remove(index);
+                continue;
+            };
+            match &event {
+                DroneEvent::PacketSent(packet) if matches!(packet.pack_type, PacketType::MsgFragment(_)) => {
+                    *unreported.entry(*drone).or_default() -= 1;
+                    if let Some(next_hop) = packet.routing_header.current_hop().filter(|id| network.implementation(*id).is_some()) {
+                        *unreported.entry(next_hop).or_default() += 1;
+                    }
+                },
+                DroneEvent::PacketDropped(packet) if matches!(packet.pack_type, PacketType::MsgFragment(_)) => {
+                    *unreported.entry(*drone).or_default() -= 1;
+                },
+                _ => {}
+            }
+            events.push((*drone, event));
+        }
+    }
+
+    report.implementations = summarize(network, &events, &pending, &unexpected_nacks);
+    report
+}
+
+fn summarize(
+    network: &Network,
+    events: &[(NodeId, DroneEvent)],
+    pending: &HashMap<(u64, u64), Vec<NodeId>>,
+    unexpected_nacks: &[(NodeId, NackType)],
+) -> Vec<ImplementationReport> {
+    // handled and dropped fragments by drone
+    let mut handled: HashMap<NodeId, u64> = HashMap::new();
+    let mut dropped: HashMap<NodeId, u64> = HashMap::new();
+    let mut lost: HashMap<NodeId, u64> = HashMap::new();
+    // furthest hop index reached by every fragment
+    let mut reached: HashMap<(u64, u64), usize> = HashMap::new();
+
+    for (drone, event) in events.iter() {
+        match event {
+            DroneEvent::PacketSent(packet) if matches!(packet.pack_type, PacketType::MsgFragment(_)) => {
+                *handled.entry(*drone).or_default() += 1;
+                let key = (packet.session_id, packet.get_fragment_index());
+                let hop_index = reached.entry(key).or_default();
+                *hop_index = (*hop_index).max(packet.routing_header.hop_index);
+            },
+            DroneEvent::PacketDropped(packet) if matches!(packet.pack_type, PacketType::MsgFragment(_)) => {
+                *handled.entry(*drone).or_default() += 1;
+                *dropped.entry(*drone).or_default() += 1;
+            },
+            _ => {}
+        }
+    }
+
+    for (key, route) in pending.iter() {
+        let hop_index = reached.get(key).copied().unwrap_or(1);
+        if let Some(drone) = route.get(hop_index).filter(|id| network.implementation(**id).is_some()) {
+            *handled.entry(*drone).or_default() += 1;
+            *lost.entry(*drone).or_default() += 1;
+        }
+    }
+
+    let mut reports: BTreeMap<&str, ImplementationReport> = BTreeMap::new();
+    for drone in network.drones() {
+        let implementation = network.implementation(drone).unwrap_or_default();
+        let report = reports.entry(implementation).or_insert_with(|| ImplementationReport {
+            implementation: implementation.to_string(),
+            ..Default::default()
+        });
+
+        let pdr = network.pdr(drone).unwrap_or_default() as f64;
+        let drone_handled = handled.get(&drone).copied().unwrap_or_default();
+        report.drones.push(drone);
+        report.handled += drone_handled;
+        report.dropped += dropped.get(&drone).copied().unwrap_or_default();
+        report.lost += lost.get(&drone).copied().unwrap_or_default();
+        report.expected_drops += drone_handled as f64 * pdr;
+        report.drops_variance += drone_handled as f64 * pdr * (1.0 - pdr);
+        report.unexpected_nacks.extend(unexpected_nacks.iter().filter(|(origin, _)| *origin == drone));
+    }
+
+    reports.into_values().collect()
+}
@@ -0,0 +1,390 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use crossbeam_channel::{unbounded, Receiver, Select, Sender};
+use log::{error, warn};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NodeType, Packet};
+use crate::drone::stats::DroneStats;
+use crate::drone::topology::shortest_route;
+
+pub mod chaos;
+pub mod checksum;
+pub mod config;
+pub mod dot;
+pub mod endpoint;
+pub mod interop;
+pub mod partition;
+pub mod registry;
+pub mod shell;
+
+pub use chaos::{ChaosAction, ChaosConfig, ChaosConfigError, ChaosEngine};
+pub use checksum::ChecksumError;
+pub use config::{DroneEntry, NetworkConfig};
+pub use dot::DotGraph;
+pub use endpoint::{EndpointEvent, Endpoints, EndpointStats, Message};
+pub use partition::{Partition, PartitionError};
+pub use registry::{DroneChannels, DroneRegistry};
+pub use shell::{Shell, ShellCommand, ShellError};
+
+/// Why a network could not be started
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    /// A drone names an implementation missing from the registry
+    UnknownImplementation { drone: NodeId, implementation: String },
+    /// Two nodes of the topology have the same id
+    DuplicateNode(NodeId),
+    /// A node is connected to an id missing from the topology
+    UnknownNode(NodeId),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::UnknownImplementation { drone, implementation } => {
+                write!(f, "drone {} uses unknown implementation {}", drone, implementation)
+            },
+            NetworkError::DuplicateNode(id) => write!(f, "node {} is defined twice", id),
+            NetworkError::UnknownNode(id) => write!(f, "node {} is not defined", id),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+#[derive(Debug)]
+struct DroneHandle {
+    implementation: String,
+    pdr: f32,
+    command_send: Sender<DroneCommand>,
+    event_recv: Receiver<DroneEvent>,
+    stats: Arc<DroneStats>,
+    crashed: bool,
+    senders: HashMap<NodeId, Sender<Packet>>,
+}
+
+/// A running network: every drone runs in its own thread, while clients and
+/// servers are only channels that the owner of the network reads and writes.
+///
+/// The network plays the role of the simulation controller: it keeps a command
+/// and an event channel for each drone and the links of the topology.
+#[derive(Debug)]
+pub struct Network {
+    nodes: BTreeMap<NodeId, NodeType>,
+    drones: BTreeMap<NodeId, DroneHandle>,
+    channels: HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)>,
+    links: BTreeMap<NodeId, BTreeSet<NodeId>>,
+    cuts: BTreeMap<(NodeId, NodeId), (usize, Sender<Packet>)>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Network {
+
+    /// Starts the network described by the config, building every drone with
+    /// the implementation it names in the registry
+    pub fn start(config: &NetworkConfig, registry: &DroneRegistry) -> Result<Self, NetworkError> {
+        let mut nodes = BTreeMap::new();
+        let endpoints = config.client.iter().map(|c| (c.id, NodeType::Client, &c.connected_drone_ids))
+            .chain(config.server.iter().map(|s| (s.id, NodeType::Server, &s.connected_drone_ids)));
+        let all = config.drone.iter()
+            .map(|d| (d.id, NodeType::Drone, &d.connected_node_ids))
+            .chain(endpoints)
+            .collect::<Vec<_>>();
+
+        for (id, node_type, _) in all.iter() {
+            if nodes.insert(*id, *node_type).is_some() {
+                return Err(NetworkError::DuplicateNode(*id));
+            }
+        }
+
+        // the links are undirected, a node can be listed by only one of the two ends
+        let mut links: BTreeMap<NodeId, BTreeSet<NodeId>> = nodes.keys().map(|id| (*id, BTreeSet::new())).collect();
+        for (id, _, neighbours) in all.iter() {
+            for neighbour in neighbours.iter() {
+                if !nodes.contains_key(neighbour) {
+                    return Err(NetworkError::UnknownNode(*neighbour));
+                }
+                links.entry(*id).or_default().insert(*neighbour);
+                links.entry(*neighbour).or_default().insert(*id);
+            }
+        }
+
+        let mut factories = Vec::new();
+        for drone in config.drone.iter() {
+            match registry.get(drone.implementation()) {
+                Some(factory) => factories.push((drone, factory)),
+                None => return Err(NetworkError::UnknownImplementation {
+                    drone: drone.id,
+                    implementation: drone.implementation().to_string(),
+                }),
+            }
+        }
+
+        let mut network = Self {
+            channels: nodes.keys().map(|id| (*id, unbounded())).collect(),
+            nodes,
+            drones: BTreeMap::new(),
+            links,
+            cuts: BTreeMap::new(),
+            threads: Vec::new(),
+        };
+
+        for (drone, factory) in factories {
+            let (controller_send, event_recv) = unbounded();
+            let (command_send, controller_recv) = unbounded();
+            let channels = DroneChannels {
+                controller_send,
+                controller_recv,
+                packet_recv: network.channels[&drone.id].1.clone(),
+                packet_send: network.links[&drone.id]
+                    .iter()
+                    .map(|neighbour| (*neighbour, network.channels[neighbour].0.clone()))
+                    .collect(),
+                stats: Arc::new(DroneStats::default()),
+            };
+            let stats = Arc::clone(&channels.stats);
+            let senders = channels.packet_send.clone();
+
+            let run = factory(drone.id, channels, drone.pdr);
+            network.threads.push(thread::spawn(run));
+            network.drones.insert(drone.id, DroneHandle {
+                implementation: drone.implementation().to_string(),
+                pdr: drone.pdr,
+                command_send,
+                event_recv,
+                stats,
+                crashed: false,
+                senders,
+            });
+        }
+
+        Ok(network)
+    }
+
+    /// Returns the type of the node with the given id
+    pub fn node_type(&self, id: NodeId) -> Option<NodeType> {
+        self.nodes.get(&id).copied()
+    }
+
+    /// Returns the ids of every node, sorted
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.keys().copied()
+    }
+
+    /// Returns the ids of the drones, sorted
+    pub fn drones(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.drones.keys().copied()
+    }
+
+    /// Returns the ids of the clients, sorted
+    pub fn clients(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes_of_type(NodeType::Client)
+    }
+
+    /// Returns the ids of the servers, sorted
+    pub fn servers(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes_of_type(NodeType::Server)
+    }
+
+    fn nodes_of_type(&self, node_type: NodeType) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .iter()
+            .filter(move |(_, t)| **t == node_type)
+            .map(|(id, _)| *id)
+    }
+
+    /// Returns the name of the implementation of a drone
+    pub fn implementation(&self, drone: NodeId) -> Option<&str> {
+        self.drones.get(&drone).map(|handle| handle.implementation.as_str())
+    }
+
+    /// Returns the last packet drop rate set on a drone
+    pub fn pdr(&self, drone: NodeId) -> Option<f32> {
+        self.drones.get(&drone).map(|handle| handle.pdr)
+    }
+
+    /// Returns the counters of a drone. They stay at zero for the implementations
+    /// that do not update them
+    pub fn stats(&self, drone: NodeId) -> Option<Arc<DroneStats>> {
+        self.drones.get(&drone).map(|handle| Arc::clone(&handle.stats))
+    }
+
+    /// Returns true if the drone has been crashed
+    pub fn is_crashed(&self, drone: NodeId) -> bool {
+        self.drones.get(&drone).is_some_and(|handle| handle.crashed)
+    }
+
+    /// Returns the current neighbours of a node, following the `AddSender`
+    /// and `RemoveSender` commands sent through the network
+    pub fn neighbours(&self, id: NodeId) -> BTreeSet<NodeId> {
+        self.links.get(&id).cloned().unwrap_or_default()
+    }
+
+    /// Returns a sender to the channel of the given node
+    pub fn sender(&self, id: NodeId) -> Option<Sender<Packet>> {
+        self.channels.get(&id).map(|(sender, _)| sender.clone())
+    }
+
+    /// Returns the receiving end of the channel of a client or a server
+    pub fn receiver(&self, id: NodeId) -> Option<Receiver<Packet>> {
+        match self.node_type(id) {
+            Some(NodeType::Client) | Some(NodeType::Server) => {
+                self.channels.get(&id).map(|(_, receiver)| receiver.clone())
+            },
+            _ => None,
+        }
+    }
+
+    /// Puts a packet in the channel of the given node.
+    /// Returns false if the node does not exist
+    pub fn send(&self, to: NodeId, packet: Packet) -> bool {
+        match self.channels.get(&to) {
+            Some((sender, _)) => sender.send(packet).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Sends a command to a drone. Returns false if the drone does not exist or has crashed
+    pub fn send_command(&mut self, drone: NodeId, command: DroneCommand) -> bool {
+        let Some(handle) = self.drones.get_mut(&drone) else {
+            warn!("Network could not send command to unknown drone {}", drone);
+            return false;
+        };
+        if handle.crashed {
+            warn!("Network could not send command to crashed drone {}", drone);
+            return false;
+        }
+
+        match &command {
+            DroneCommand::SetPacketDropRate(pdr) if (0.0..=1.0).contains(pdr) => handle.pdr = *pdr,
+            DroneCommand::Crash => handle.crashed = true,
+            // the links follow the neighbour maps of the drones
+            DroneCommand::AddSender(neighbour, sender) if self.nodes.contains_key(neighbour) => {
+                handle.senders.insert(*neighbour, sender.clone());
+                self.links.entry(drone).or_default().insert(*neighbour);
+                self.links.entry(*neighbour).or_default().insert(drone);
+            },
+            DroneCommand::RemoveSender(neighbour) => {
+                handle.senders.remove(neighbour);
+                self.links.entry(drone).or_default().remove(neighbour);
+                if let Some(links) = self.links.get_mut(neighbour) {
+                    links.remove(&drone);
+                }
+            },
+            _ => {}
+        }
+
+        if handle.command_send.send(command).is_err() {
+            error!("Network could not send command to drone {}", drone);
+            return false;
+        }
+        true
+    }
+
+    /// Crashes a drone after its neighbours removed it, as the protocol requires.
+    /// Returns false if the drone does not exist or has already crashed
+    pub fn crash(&mut self, drone: NodeId) -> bool {
+        if !self.is_running_drone(drone) {
+            warn!("Network could not crash drone {}", drone);
+            return false;
+        }
+        for neighbour in self.neighbours(drone) {
+            if self.is_running_drone(neighbour) {
+                self.send_command(neighbour, DroneCommand::RemoveSender(drone));
+            }
+        }
+        self.send_command(drone, DroneCommand::Crash)
+    }
+
+    /// Connects two nodes, giving each running drone among them a sender towards the other.
+    /// Returns false if no running drone is at one of the ends
+    pub fn link(&mut self, a: NodeId, b: NodeId) -> bool {
+        let mut linked = false;
+        for (drone, neighbour) in [(a, b), (b, a)] {
+            if a == b || !self.is_running_drone(drone) {
+                continue;
+            }
+            if let Some(sender) = self.sender(neighbour) {
+                linked |= self.send_command(drone, DroneCommand::AddSender(neighbour, sender));
+            }
+        }
+        linked
+    }
+
+    /// Disconnects two nodes, removing the sender of each running drone among them
+    /// towards the other. Returns false if no running drone is at one of the ends
+    pub fn unlink(&mut self, a: NodeId, b: NodeId) -> bool {
+        let mut unlinked = false;
+        for (drone, neighbour) in [(a, b), (b, a)] {
+            if self.is_running_drone(drone) {
+                unlinked |= self.send_command(drone, DroneCommand::RemoveSender(neighbour));
+            }
+        }
+        unlinked
+    }
+
+    /// Blocks until a packet waits at a client or a server, or a running drone sent
+    /// an event. Returns false if the deadline passed first. Nothing is received,
+    /// the packets and the events are left to their readers
+    pub fn wait(&self, deadline: Instant) -> bool {
+        let mut select = Select::new();
+        for id in self.clients().chain(self.servers()) {
+            if let Some((_, receiver)) = self.channels.get(&id) {
+                select.recv(receiver);
+            }
+        }
+        for handle in self.drones.values().filter(|handle| !handle.crashed) {
+            select.recv(&handle.event_recv);
+        }
+        select.ready_deadline(deadline).is_ok()
+    }
+
+    /// Returns the events sent by the drones since the last call, grouped by drone
+    pub fn events(&self) -> Vec<(NodeId, DroneEvent)> {
+        let mut events = Vec::new();
+        for (id, handle) in self.drones.iter() {
+            while let Ok(event) = handle.event_recv.try_recv() {
+                events.push((*id, event));
+            }
+        }
+        events
+    }
+
+    /// Returns the shortest route between two nodes, made only of running drones
+    /// between the two ends
+    pub fn route(&self, from: NodeId, to: NodeId) -> Option<Vec<NodeId>> {
+        shortest_route(from, to, |node| self.neighbours(node), |node| self.is_running_drone(node))
+    }
+
+    fn is_running_drone(&self, id: NodeId) -> bool {
+        self.drones.get(&id).is_some_and(|handle| !handle.crashed)
+    }
+
+    /// Crashes every drone and waits for their threads to end
+    pub fn shutdown(mut self) {
+        self.crash_all();
+        for thread in self.threads.drain(..) {
+            if thread.join().is_err() {
+                error!("Network found a drone thread that panicked");
+            }
+        }
+    }
+
+    fn crash_all(&mut self) {
+        for handle in self.drones.values_mut() {
+            if !handle.crashed {
+                handle.command_send.send(DroneCommand::Crash).ok();
+                handle.crashed = true;
+            }
+        }
+    }
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        self.crash_all();
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use log::{info, warn};
+use wg_2024::controller::DroneCommand;
+use wg_2024::network::NodeId;
+use super::Network;
+
+/// Why a network could not be partitioned
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartitionError {
+    UnknownNode(NodeId),
+    /// A node is listed in two groups, named here
+    NodeInTwoGroups { node: NodeId, first: String, second: String },
+}
+
+impl fmt::Display for PartitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionError::UnknownNode(id) => write!(f, "node {} does not exist", id),
+            PartitionError::NodeInTwoGroups { node, first, second } => {
+                write!(f, "node {} is in both group {} and group {}", node, first, second)
+            },
+        }
+    }
+}
+
+impl std::error::Error for PartitionError {}
+
+/// A partition of a network into named groups, keeping the links it cut
+/// so that `Network::heal` can restore them.
+///
+/// Partitions can overlap: a link cut by several of them is restored when
+/// the last one is healed, in whatever order they are healed
+#[derive(Debug)]
+pub struct Partition {
+    groups: BTreeMap<NodeId, String>,       // The group of every listed node
+    removed: Vec<(NodeId, NodeId)>,         // The drone and the neighbour removed from its `packet_send`
+}
+
+impl Partition {
+    /// Returns the name of the group of a node, `None` if it was not listed
+    pub fn group_of(&self, id: NodeId) -> Option<&str> {
+        self.groups.get(&id).map(String::as_str)
+    }
+
+    /// Returns the links cut by the partition, each once with the lower id first
+    pub fn cut_links(&self) -> Vec<(NodeId, NodeId)> {
+        let mut links = self.removed
+            .iter()
+            .map(|(drone, neighbour)| (*drone.min(neighbour), *drone.max(neighbour)))
+            .collect::<Vec<_>>();
+        links.sort_unstable();
+        links.dedup();
+        links
+    }
+}
+
+impl Network {
+    /// Splits the network into named groups by removing from every drone the
+    /// senders it was given towards the nodes of the other groups. The nodes
+    /// not listed form one more group of their own
+    pub fn partition(&mut self, groups: &[(&str, &[NodeId])]) -> Result<Partition, PartitionError> {
+        let mut group_of = BTreeMap::new();
+        for (name, nodes) in groups {
+            for node in nodes.iter() {
+                if !self.nodes.contains_key(node) {
+                    return Err(PartitionError::UnknownNode(*node));
+                }
+                if let Some(first) = group_of.insert(*node, name.to_string()) {
+                    return Err(PartitionError::NodeInTwoGroups {
+                        node: *node,
+                        first,
+                        second: name.to_string(),
+                    });
+                }
+            }
+        }
+
+        // each drone removes the senders it was given towards the other groups,
+        // the links already cut by another partition stay cut until both are healed
+        let crossing = self.drones
+            .iter()
+            .filter(|(_, handle)| !handle.crashed)
+            .flat_map(|(drone, handle)| handle.senders.keys().map(move |neighbour| (*drone, *neighbour)))
+            .filter(|(drone, neighbour)| group_of.get(drone) != group_of.get(neighbour))
+            .collect::<Vec<_>>();
+        let mut removed = self.cuts
+            .iter_mut()
+            .filter(|((drone, neighbour), _)| group_of.get(drone) != group_of.get(neighbour))
+            .map(|(link, (partitions, _))| {
+                *partitions += 1;
+                *link
+            })
+            .collect::<Vec<_>>();
+
+        for (drone, neighbour) in crossing {
+            let Some(sender) = self.drones.get(&drone).and_then(|handle| handle.senders.get(&neighbour)).cloned() else {
+                continue;
+            };
+            // a link restored by hand while cut was already counted above
+            if self.send_command(drone, DroneCommand::RemoveSender(neighbour)) && !self.cuts.contains_key(&(drone, neighbour)) {
+                self.cuts.insert((drone, neighbour), (1, sender));
+                removed.push((drone, neighbour));
+            }
+        }
+
+        let partition = Partition { groups: group_of, removed };
+        info!("Network partitioned, cutting links {:?}", partition.cut_links());
+        Ok(partition)
+    }
+
+    /// Gives back to the drones the senders removed by a partition, unless another
+    /// partition not healed yet cut the same link. The links of the drones crashed
+    /// in the meantime are not restored
+    pub fn heal(&mut self, partition: Partition) {
+        for link in partition.removed {
+            let Some((partitions, _)) = self.cuts.get_mut(&link) else {
+                continue;
+            };
+            *partitions -= 1;
+            if *partitions > 0 {
+                continue;
+            }
+            let Some((_, sender)) = self.cuts.remove(&link) else {
+                continue;
+            };
+            let (drone, neighbour) = link;
+            if self.is_crashed(drone) || self.is_crashed(neighbour) {
+                continue;
+            }
+            if !self.send_command(drone, DroneCommand::AddSender(neighbour, sender)) {
+                warn!("Network could not restore the link from {} to {}", drone, neighbour);
+            }
+        }
+        info!("Network healed");
+    }
+}
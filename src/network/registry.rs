@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crossbeam_channel::{Receiver, Sender};
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+use crate::drone::RustDoIt;
+use crate::drone::stats::DroneStats;
+use super::config::DEFAULT_IMPLEMENTATION;
+
+/// The arguments of `wg_2024::drone::Drone::new`, and the counters that
+/// the network reads from the implementations able to update them
+pub struct DroneChannels {
+    pub controller_send: Sender<DroneEvent>,
+    pub controller_recv: Receiver<DroneCommand>,
+    pub packet_recv: Receiver<Packet>,
+    pub packet_send: HashMap<NodeId, Sender<Packet>>,
+    pub stats: Arc<DroneStats>,
+}
+
+/// Builds a drone and returns the closure running it, to be moved to its own thread
+pub type DroneFactory = fn(NodeId, DroneChannels, f32) -> Box<dyn FnOnce() + Send>;
+
+/// The drone implementations available to a network, by name
+#[derive(Debug, Clone)]
+pub struct DroneRegistry {
+    factories: HashMap<String, DroneFactory>,
+}
+
+impl DroneRegistry {
+    /// Creates a registry with no implementation
+    pub fn empty() -> Self {
+        Self { factories: HashMap::new() }
+    }
+
+    /// Registers the implementation `D` under the given name,
+    /// replacing the one previously registered with the same name
+    pub fn register<D: Drone + 'static>(&mut self, name: &str) -> &mut Self {
+        self.register_factory(name, build::<D>)
+    }
+
+    /// Registers a factory under the given name, for the implementations that
+    /// need more than `Drone::new` to be built
+    pub fn register_factory(&mut self, name: &str, factory: DroneFactory) -> &mut Self {
+        self.factories.insert(name.to_string(), factory);
+        self
+    }
+
+    /// Returns the factory of the implementation with the given name
+    pub fn get(&self, name: &str) -> Option<DroneFactory> {
+        self.factories.get(name).copied()
+    }
+
+    /// Returns the names of the registered implementations, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.factories.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+}
+
+impl Default for DroneRegistry {
+    /// Creates a registry containing `RustDoIt` under the default implementation name,
+    /// updating the counters of the network
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register_factory(DEFAULT_IMPLEMENTATION, build_rust_do_it);
+        registry
+    }
+}
+
+fn build<D: Drone + 'static>(id: NodeId, channels: DroneChannels, pdr: f32) -> Box<dyn FnOnce() + Send> {
+    // the drone is created in the closure, so that it does not need to be `Send`
+    Box::new(move || {
+        let mut drone = D::new(
+            id,
+            channels.controller_send,
+            channels.controller_recv,
+            channels.packet_recv,
+            channels.packet_send,
+            pdr,
+        );
+        drone.run();
+    })
+}
+
+fn build_rust_do_it(id: NodeId, channels: DroneChannels, pdr: f32) -> Box<dyn FnOnce() + Send> {
+    Box::new(move || {
+        let mut drone = RustDoIt::new(
+            id,
+            channels.controller_send,
+            channels.controller_recv,
+            channels.packet_recv,
+            channels.packet_send,
+            pdr,
+        ).with_stats(channels.stats);
+        drone.run();
+    })
+}
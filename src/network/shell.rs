@@ -0,0 +1,376 @@
+use std::fmt;
+use std::fmt::Write as _;
+use std::str::FromStr;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::NodeId;
+use wg_2024::packet::{NodeType, Packet, PacketType};
+use super::endpoint::{EndpointEvent, Endpoints};
+use super::Network;
+
+/// The help of the shell, one line per command
+pub const HELP: &str = "\
+crash <drone>              crash a drone and remove it from its neighbours
+pdr <drone> <rate>         set the packet drop rate of a drone
+link <a> <b>               connect two nodes, at least one must be a drone
+unlink <a> <b>             disconnect two nodes
+send <from> <to> <text>    send a message between two endpoints, quote the text to keep its spaces
+flood <endpoint>           start a flood from a client or a server
+stats                      print the counters of the drones and of the endpoints
+trace on|off               print the events of the drones as they arrive
+help                       print this message
+quit                       stop the network and exit";
+
+/// A command of the interactive shell
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShellCommand {
+    Crash(NodeId),
+    Pdr(NodeId, f32),
+    Link(NodeId, NodeId),
+    Unlink(NodeId, NodeId),
+    Send { from: NodeId, to: NodeId, text: String },
+    Flood(NodeId),
+    Stats,
+    Trace(bool),
+    Help,
+    Quit,
+}
+
+/// Why a line of the shell could not be parsed or executed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShellError {
+    /// The line has no command
+    Empty,
+    UnknownCommand(String),
+    /// The command is missing an argument, named here
+    MissingArgument(&'static str),
+    InvalidArgument(String),
+    /// A quote of the line is never closed
+    UnclosedQuote,
+    UnknownNode(NodeId),
+    NotADrone(NodeId),
+    NotAnEndpoint(NodeId),
+    /// The drone has crashed and does not accept commands anymore
+    Crashed(NodeId),
+    /// A link needs at least a drone at one of its ends
+    InvalidLink(NodeId, NodeId),
+    Unroutable { from: NodeId, to: NodeId },
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShellError::Empty => write!(f, "no command given"),
+            ShellError::UnknownCommand(command) => write!(f, "unknown command {}, try help", command),
+            ShellError::MissingArgument(name) => write!(f, "missing argument <{}>", name),
+            ShellError::InvalidArgument(argument) => write!(f, "invalid argument {}", argument),
+            ShellError::UnclosedQuote => write!(f, "unclosed quote"),
+            ShellError::UnknownNode(id) => write!(f, "node {} does not exist", id),
+            ShellError::NotADrone(id) => write!(f, "node {} is not a drone", id),
+            ShellError::NotAnEndpoint(id) => write!(f, "node {} is not a client or a server", id),
+            ShellError::Crashed(id) => write!(f, "drone {} has crashed", id),
+            ShellError::InvalidLink(a, b) => write!(f, "nodes {} and {} cannot be linked", a, b),
+            ShellError::Unroutable { from, to } => write!(f, "no route from {} to {}", from, to),
+        }
+    }
+}
+
+impl std::error::Error for ShellError {}
+
+impl FromStr for ShellCommand {
+    type Err = ShellError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let words = split_words(line)?;
+        let mut words = words.iter().map(String::as_str);
+        let command = words.next().ok_or(ShellError::Empty)?;
+
+        let command = match command {
+            "crash" => ShellCommand::Crash(argument(&mut words, "drone")?),
+            "pdr" => ShellCommand::Pdr(argument(&mut words, "drone")?, argument(&mut words, "rate")?),
+            "link" => ShellCommand::Link(argument(&mut words, "a")?, argument(&mut words, "b")?),
+            "unlink" => ShellCommand::Unlink(argument(&mut words, "a")?, argument(&mut words, "b")?),
+            "send" => ShellCommand::Send {
+                from: argument(&mut words, "from")?,
+                to: argument(&mut words, "to")?,
+                text: words.by_ref().collect::<Vec<_>>().join(" "),
+            },
+            "flood" => ShellCommand::Flood(argument(&mut words, "endpoint")?),
+            "stats" => ShellCommand::Stats,
+            "trace" => match words.next() {
+                Some("on") => ShellCommand::Trace(true),
+                Some("off") => ShellCommand::Trace(false),
+                Some(other) => return Err(ShellError::InvalidArgument(other.to_string())),
+                None => return Err(ShellError::MissingArgument("on|off")),
+            },
+            "help" => ShellCommand::Help,
+            "quit" | "exit" => ShellCommand::Quit,
+            other => return Err(ShellError::UnknownCommand(other.to_string())),
+        };
+
+        match words.next() {
+            Some(extra) => Err(ShellError::InvalidArgument(extra.to_string())),
+            None => Ok(command),
+        }
+    }
+}
+
+fn split_words(line: &str) -> Result<Vec<String>, ShellError> {
+    // This function splits a line on whitespace, keeping together the
+    // words between double quotes
+    // ### Parameters:
+    // - `line`: The line to be split
+
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            },
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        return Err(ShellError::UnclosedQuote);
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+fn argument<'a, T: FromStr>(words: &mut impl Iterator<Item = &'a str>, name: &'static str) -> Result<T, ShellError> {
+    let word = words.next().ok_or(ShellError::MissingArgument(name))?;
+    word.parse().map_err(|_| ShellError::InvalidArgument(word.to_string()))
+}
+
+/// An interactive controller of a running network.
+///
+/// Every command is mapped onto the `DroneCommand`s of the protocol, while the
+/// clients and the servers are played by `Endpoints`. The output of the commands,
+/// and what happens in the network afterwards, is returned as text
+#[derive(Debug)]
+pub struct Shell {
+    network: Network,
+    endpoints: Endpoints,
+    trace: bool,
+}
+
+impl Shell {
+
+    pub fn new(network: Network) -> Self {
+        Self {
+            endpoints: Endpoints::new(&network),
+            network,
+            trace: false,
+        }
+    }
+
+    /// Returns the network controlled by the shell
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Parses and executes a line, returning its output
+    pub fn execute_line(&mut self, line: &str) -> Result<String, ShellError> {
+        let command = line.parse()?;
+        self.execute(command)
+    }
+
+    /// Executes a command, returning its output
+    pub fn execute(&mut self, command: ShellCommand) -> Result<String, ShellError> {
+        match command {
+            ShellCommand::Crash(drone) => {
+                self.check_drone(drone)?;
+                self.network.crash(drone);
+                Ok(format!("drone {} crashed", drone))
+            },
+            ShellCommand::Pdr(drone, pdr) => {
+                self.check_drone(drone)?;
+                if !(0.0..=1.0).contains(&pdr) {
+                    return Err(ShellError::InvalidArgument(pdr.to_string()));
+                }
+                self.network.send_command(drone, DroneCommand::SetPacketDropRate(pdr));
+                Ok(format!("drone {} drops {:.2} of the fragments", drone, pdr))
+            },
+            ShellCommand::Link(a, b) => {
+                self.check_link(a, b)?;
+                self.network.link(a, b);
+                Ok(format!("linked {} and {}", a, b))
+            },
+            ShellCommand::Unlink(a, b) => {
+                self.check_link(a, b)?;
+                self.network.unlink(a, b);
+                Ok(format!("unlinked {} and {}", a, b))
+            },
+            ShellCommand::Send { from, to, text } => {
+                self.check_endpoint(from)?;
+                self.check_endpoint(to)?;
+                let session_id = self.endpoints
+                    .send(&self.network, from, to, text.as_bytes())
+                    .ok_or(ShellError::Unroutable { from, to })?;
+                Ok(format!("sent session {} from {} to {}", session_id, from, to))
+            },
+            ShellCommand::Flood(endpoint) => {
+                self.check_endpoint(endpoint)?;
+                let flood_id = self.endpoints
+                    .flood(&self.network, endpoint)
+                    .ok_or(ShellError::NotAnEndpoint(endpoint))?;
+                Ok(format!("flood {} started from {}", flood_id, endpoint))
+            },
+            ShellCommand::Stats => Ok(stats_table(&self.network, &self.endpoints)),
+            ShellCommand::Trace(trace) => {
+                self.trace = trace;
+                Ok(format!("trace {}", if trace { "on" } else { "off" }))
+            },
+            ShellCommand::Help => Ok(HELP.to_string()),
+            ShellCommand::Quit => Ok("bye".to_string()),
+        }
+    }
+
+    /// Handles what happened in the network since the last call, returning
+    /// a line for every endpoint event, and for every drone event if the trace is on
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let events = self.network.events();
+        self.endpoints.handle_events(&self.network, &events);
+        if self.trace {
+            lines.extend(events.iter().map(|(drone, event)| describe_drone_event(*drone, event)));
+        }
+        lines.extend(self.endpoints.poll(&self.network).iter().map(describe_endpoint_event));
+        lines
+    }
+
+    /// Crashes every drone and waits for their threads to end
+    pub fn shutdown(self) {
+        self.network.shutdown();
+    }
+
+    fn check_drone(&self, id: NodeId) -> Result<(), ShellError> {
+        match self.network.node_type(id) {
+            None => Err(ShellError::UnknownNode(id)),
+            Some(NodeType::Drone) if self.network.is_crashed(id) => Err(ShellError::Crashed(id)),
+            Some(NodeType::Drone) => Ok(()),
+            Some(_) => Err(ShellError::NotADrone(id)),
+        }
+    }
+
+    fn check_endpoint(&self, id: NodeId) -> Result<(), ShellError> {
+        match self.network.node_type(id) {
+            None => Err(ShellError::UnknownNode(id)),
+            Some(NodeType::Drone) => Err(ShellError::NotAnEndpoint(id)),
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn check_link(&self, a: NodeId, b: NodeId) -> Result<(), ShellError> {
+        let mut drones = 0;
+        for id in [a, b] {
+            match self.network.node_type(id) {
+                None => return Err(ShellError::UnknownNode(id)),
+                Some(NodeType::Drone) => {
+                    self.check_drone(id)?;
+                    drones += 1;
+                },
+                Some(_) => {},
+            }
+        }
+        if drones == 0 || a == b {
+            return Err(ShellError::InvalidLink(a, b));
+        }
+        Ok(())
+    }
+}
+
+/// Renders the counters of the drones of a network and of its endpoints as a table
+pub fn stats_table(network: &Network, endpoints: &Endpoints) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{:>6} {:<16} {:>6} {:>10} {:>8} {:>6}",
+        "drone", "implementation", "pdr", "forwarded", "dropped", "nacks"
+    ).ok();
+    for drone in network.drones() {
+        let Some(stats) = network.stats(drone) else {
+            continue;
+        };
+        writeln!(
+            out,
+            "{:>6} {:<16} {:>6.2} {:>10} {:>8} {:>6}{}",
+            drone,
+            network.implementation(drone).unwrap_or_default(),
+            network.pdr(drone).unwrap_or_default(),
+            stats.packets_forwarded(),
+            stats.packets_dropped(),
+            stats.nacks_total(),
+            if network.is_crashed(drone) { "  crashed" } else { "" }
+        ).ok();
+    }
+
+    let stats = endpoints.stats();
+    writeln!(out).ok();
+    writeln!(
+        out,
+        "messages: {} sent, {} delivered, {} unroutable, {} corrupted",
+        stats.messages_sent, stats.messages_delivered, stats.messages_unroutable, stats.messages_corrupted
+    ).ok();
+    writeln!(
+        out,
+        "fragments: {} sent, {} delivered, {} acked, {} nacked",
+        stats.fragments_sent, stats.fragments_delivered, stats.acks_received, stats.nacks_received
+    ).ok();
+    write!(out, "delivery ratio: {:.3}", stats.delivery_ratio()).ok();
+    out
+}
+
+fn describe_packet(packet: &Packet) -> String {
+    let kind = match &packet.pack_type {
+        PacketType::MsgFragment(fragment) => format!("fragment {}", fragment.fragment_index),
+        PacketType::Ack(ack) => format!("ack {}", ack.fragment_index),
+        PacketType::Nack(nack) => format!("nack {:?} {}", nack.nack_type, nack.fragment_index),
+        PacketType::FloodRequest(flood_request) => format!("flood request {}", flood_request.flood_id),
+        PacketType::FloodResponse(flood_response) => format!("flood response {}", flood_response.flood_id),
+    };
+    format!("{} of session {} via {:?}", kind, packet.session_id, packet.routing_header.hops)
+}
+
+fn describe_drone_event(drone: NodeId, event: &DroneEvent) -> String {
+    match event {
+        DroneEvent::PacketSent(packet) => format!("drone {} sent {}", drone, describe_packet(packet)),
+        DroneEvent::PacketDropped(packet) => format!("drone {} dropped {}", drone, describe_packet(packet)),
+        DroneEvent::ControllerShortcut(packet) => format!("drone {} shortcut {}", drone, describe_packet(packet)),
+    }
+}
+
+fn describe_endpoint_event(event: &EndpointEvent) -> String {
+    match event {
+        EndpointEvent::Delivered(message) => format!(
+            "{} received session {} from {}: {}",
+            message.to,
+            message.session_id,
+            message.from,
+            String::from_utf8_lossy(&message.data)
+        ),
+        EndpointEvent::Acked { node, session_id, fragment_index } => {
+            format!("{} got ack {} of session {}", node, fragment_index, session_id)
+        },
+        EndpointEvent::Nacked { node, session_id, fragment_index, nack_type } => {
+            format!("{} got nack {:?} {} of session {}", node, nack_type, fragment_index, session_id)
+        },
+        EndpointEvent::FloodResponse { node, flood_id, path_trace } => format!(
+            "{} got flood response {}: {:?}",
+            node,
+            flood_id,
+            path_trace.iter().map(|(id, _)| *id).collect::<Vec<_>>()
+        ),
+        EndpointEvent::Corrupted { from, to, session_id, retransmitted } => format!(
+            "{} received corrupted session {} from {}{}",
+            to,
+            session_id,
+            from,
+            if *retransmitted { ", sent again" } else { "" }
+        ),
+    }
+}
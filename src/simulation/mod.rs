@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use log::{debug, warn};
+use wg_2024::config::Config;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::drone::Drone;
+use wg_2024::network::NodeId;
+use wg_2024::packet::Packet;
+use crate::drone::topology::shortest_route;
+use crate::drone::RustDoIt;
+
+pub mod scenario;
+
+/// An event sent by a drone to the controller, tagged with the virtual time
+/// and the id of the drone that generated it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedEvent {
+    pub time: u64,
+    pub drone: NodeId,
+    pub event: DroneEvent,
+}
+
+/// A packet that reached a client or a server, tagged with the virtual time
+/// of its arrival
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub time: u64,
+    pub node: NodeId,
+    pub packet: Packet,
+}
+
+#[derive(Debug)]
+struct SimulatedDrone {
+    drone: RustDoIt,
+    packet_recv: Receiver<Packet>,
+    event_recv: Receiver<DroneEvent>,
+    _command_send: Sender<DroneCommand>,    // Kept alive so that the drone command channel is never disconnected
+}
+
+/// Discrete-event scheduler that drives a whole network of `RustDoIt` drones
+/// in the calling thread.
+///
+/// Time advances in rounds: during round `t` every drone, in ascending id order,
+/// handles the packets that were waiting in its channel when the round started.
+/// Packets sent during round `t` are therefore handled at round `t + 1`, so each
+/// hop costs one unit of virtual time. Given the same seed, topology and inputs,
+/// two simulations produce exactly the same events.
+#[derive(Debug)]
+pub struct Simulation {
+    now: u64,
+    seed: u64,
+    drones: BTreeMap<NodeId, SimulatedDrone>,
+    crashed: BTreeSet<NodeId>,
+    endpoints: BTreeSet<NodeId>,
+    channels: HashMap<NodeId, (Sender<Packet>, Receiver<Packet>)>,
+    commands: BTreeMap<u64, Vec<(NodeId, DroneCommand)>>,
+    injections: BTreeMap<u64, Vec<(NodeId, Packet)>>,
+    events: Vec<TimedEvent>,
+    deliveries: Vec<Delivery>,
+}
+
+impl Simulation {
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            now: 0,
+            seed,
+            drones: BTreeMap::new(),
+            crashed: BTreeSet::new(),
+            endpoints: BTreeSet::new(),
+            channels: HashMap::new(),
+            commands: BTreeMap::new(),
+            injections: BTreeMap::new(),
+            events: Vec::new(),
+            deliveries: Vec::new(),
+        }
+    }
+
+    /// Builds a simulation with the drones, clients and servers described in the config.
+    /// Clients and servers are passive endpoints: the packets they receive are
+    /// recorded as deliveries, and packets they send are injected with `inject`
+    pub fn from_config(config: &Config, seed: u64) -> Self {
+        let mut simulation = Self::new(seed);
+
+        for drone in config.drone.iter() {
+            simulation.add_drone(drone.id, drone.pdr);
+        }
+        for client in config.client.iter() {
+            simulation.add_endpoint(client.id);
+        }
+        for server in config.server.iter() {
+            simulation.add_endpoint(server.id);
+        }
+
+        for drone in config.drone.iter() {
+            for neighbour in drone.connected_node_ids.iter() {
+                simulation.connect(drone.id, *neighbour);
+            }
+        }
+        for client in config.client.iter() {
+            for neighbour in client.connected_drone_ids.iter() {
+                simulation.connect(client.id, *neighbour);
+            }
+        }
+        for server in config.server.iter() {
+            for neighbour in server.connected_drone_ids.iter() {
+                simulation.connect(server.id, *neighbour);
+            }
+        }
+
+        simulation
+    }
+
+    /// Adds a drone with no neighbours to the simulation
+    pub fn add_drone(&mut self, id: NodeId, pdr: f32) {
+        let (packet_send, packet_recv) = unbounded();
+        let (event_send, event_recv) = unbounded();
+        let (command_send, command_recv) = unbounded();
+
+        let drone = RustDoIt::new(
+            id,
+            event_send,
+            command_recv,
+            packet_recv.clone(),
+            HashMap::new(),
+            pdr,
+        ).with_seed(self.seed.wrapping_mul(31).wrapping_add(id as u64));
+
+        self.channels.insert(id, (packet_send, packet_recv.clone()));
+        self.drones.insert(id, SimulatedDrone {
+            drone,
+            packet_recv,
+            event_recv,
+            _command_send: command_send,
+        });
+    }
+
+    /// Applies the builder methods of `RustDoIt` to a drone of the simulation, e.g.
+    /// `simulation.configure_drone(11, |drone| drone.with_max_flood_path(4))`.
+    /// Returns false if there is no drone with the given id
+    pub fn configure_drone(&mut self, id: NodeId, configure: impl FnOnce(RustDoIt) -> RustDoIt) -> bool {
+        match self.drones.remove(&id) {
+            Some(mut simulated) => {
+                simulated.drone = configure(simulated.drone);
+                self.drones.insert(id, simulated);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Adds a client or a server to the simulation
+    pub fn add_endpoint(&mut self, id: NodeId) {
+        self.channels.insert(id, unbounded());
+        self.endpoints.insert(id);
+    }
+
+    /// Connects two nodes in both directions. Drones are given a sender
+    /// towards the other node, endpoints do not hold any sender
+    pub fn connect(&mut self, a: NodeId, b: NodeId) {
+        for (from, to) in [(a, b), (b, a)] {
+            let Some(sender) = self.sender(to) else {
+                warn!("Simulation could not connect {} to unknown node {}", from, to);
+                continue;
+            };
+            if let Some(simulated) = self.drones.get_mut(&from) {
+                simulated.drone.handle_command(DroneCommand::AddSender(to, sender));
+            }
+        }
+    }
+
+    /// Returns a sender to the channel of the given node
+    pub fn sender(&self, node: NodeId) -> Option<Sender<Packet>> {
+        self.channels.get(&node).map(|(sender, _)| sender.clone())
+    }
+
+    /// Schedules a command to be handled by a drone at the beginning of round `at`
+    pub fn schedule_command(&mut self, at: u64, drone: NodeId, command: DroneCommand) {
+        self.commands.entry(at.max(self.now)).or_default().push((drone, command));
+    }
+
+    /// Makes a drone handle a command right away, instead of at the beginning of a round
+    pub fn send_command(&mut self, drone: NodeId, command: DroneCommand) {
+        self.apply_command(drone, command);
+    }
+
+    /// Schedules a packet to be put in the channel of node `to` at the beginning of round `at`
+    pub fn inject(&mut self, at: u64, to: NodeId, packet: Packet) {
+        self.injections.entry(at.max(self.now)).or_default().push((to, packet));
+    }
+
+    /// Executes a single round of the simulation
+    pub fn step(&mut self) {
+        let now = self.now;
+
+        for (id, command) in self.commands.remove(&now).unwrap_or_default() {
+            self.apply_command(id, command);
+        }
+
+        for (to, packet) in self.injections.remove(&now).unwrap_or_default() {
+            match self.channels.get(&to) {
+                Some((sender, _)) => { sender.send(packet).ok(); },
+                None => warn!("Simulation could not inject packet to unknown node {}", to),
+            }
+        }
+
+        // take a snapshot of the queue lengths, so that packets sent during
+        // this round are not handled before the next one
+        let pending = self.drones
+            .iter()
+            .map(|(id, simulated)| (*id, simulated.packet_recv.len()))
+            .collect::<Vec<_>>();
+
+        for (id, count) in pending {
+            if let Some(simulated) = self.drones.get_mut(&id) {
+                simulated.drone.set_clock(now);
+                for _ in 0..count {
+                    if let Ok(packet) = simulated.packet_recv.try_recv() {
+                        simulated.drone.handle_packet(packet);
+                    }
+                }
+            }
+            self.collect_events(id);
+        }
+
+        for node in self.endpoints.iter() {
+            if let Some((_, receiver)) = self.channels.get(node) {
+                while let Ok(packet) = receiver.try_recv() {
+                    self.deliveries.push(Delivery { time: now + 1, node: *node, packet });
+                }
+            }
+        }
+
+        self.now += 1;
+    }
+
+    /// Runs the simulation until the virtual time reaches `time`
+    pub fn run_until(&mut self, time: u64) {
+        while self.now < time {
+            self.step();
+        }
+    }
+
+    /// Runs the simulation until there is nothing left to do or the virtual time
+    /// reaches `max_time`. Returns true if the network became idle
+    pub fn run_until_idle(&mut self, max_time: u64) -> bool {
+        while self.now < max_time {
+            if self.is_idle() {
+                return true;
+            }
+            self.step();
+        }
+        self.is_idle()
+    }
+
+    /// Returns true if no packet is waiting to be handled and nothing is scheduled
+    pub fn is_idle(&self) -> bool {
+        self.commands.is_empty()
+            && self.injections.is_empty()
+            && self.drones.values().all(|simulated| simulated.packet_recv.is_empty())
+    }
+
+    /// Returns the current virtual time
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Returns the events generated by the drones so far, in the order they were generated
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.events
+    }
+
+    /// Returns the packets received by clients and servers so far
+    pub fn deliveries(&self) -> &[Delivery] {
+        &self.deliveries
+    }
+
+    /// Returns the drone with the given id, if it exists and has not crashed
+    pub fn drone(&self, id: NodeId) -> Option<&RustDoIt> {
+        self.drones.get(&id).map(|simulated| &simulated.drone)
+    }
+
+    /// Returns the current neighbours of a node: the senders held by a drone, or the
+    /// running drones holding a sender towards a client or a server
+    pub fn neighbours(&self, id: NodeId) -> BTreeSet<NodeId> {
+        match self.drones.get(&id) {
+            Some(simulated) => simulated.drone.core().neighbours().iter().copied().collect(),
+            None => self.drones
+                .iter()
+                .filter(|(_, simulated)| simulated.drone.core().neighbours().contains(&id))
+                .map(|(drone, _)| *drone)
+                .collect(),
+        }
+    }
+
+    /// Returns the shortest route between two nodes, made only of running drones
+    /// between the two ends. Ties are broken by the lowest ids
+    pub fn route(&self, from: NodeId, to: NodeId) -> Option<Vec<NodeId>> {
+        shortest_route(from, to, |node| self.neighbours(node), |node| self.drones.contains_key(&node))
+    }
+
+    /// Returns true if the drone with the given id handled a `DroneCommand::Crash`
+    pub fn is_crashed(&self, id: NodeId) -> bool {
+        self.crashed.contains(&id)
+    }
+
+    fn apply_command(&mut self, id: NodeId, command: DroneCommand) {
+        let Some(simulated) = self.drones.get_mut(&id) else {
+            warn!("Simulation could not send command to drone {}", id);
+            return;
+        };
+
+        let crash = matches!(command, DroneCommand::Crash);
+        simulated.drone.handle_command(command);
+        self.collect_events(id);
+
+        if crash {
+            // the drone stops running, exactly as it returns from `run`
+            self.drones.remove(&id);
+            self.crashed.insert(id);
+            debug!("Simulation removed crashed drone {}", id);
+        }
+    }
+
+    fn collect_events(&mut self, id: NodeId) {
+        if let Some(simulated) = self.drones.get(&id) {
+            while let Ok(event) = simulated.event_recv.try_recv() {
+                self.events.push(TimedEvent { time: self.now, drone: id, event });
+            }
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use log::warn;
+use serde::Deserialize;
+use wg_2024::config::Config;
+use wg_2024::controller::{DroneCommand, DroneEvent};
+use wg_2024::network::{NodeId, SourceRoutingHeader};
+use wg_2024::packet::{Fragment, Packet, PacketType};
+use crate::drone::pdr_schedule::PdrSchedule;
+use crate::network::NetworkConfig;
+use super::Simulation;
+
+fn default_duration() -> u64 {
+    1000
+}
+
+fn one() -> u64 {
+    1
+}
+
+/// A reproducible experiment: a topology in the format of `config.toml`,
+/// a timeline of actions and the expected outcome.
+///
+/// ```toml
+/// seed = 7
+/// duration = 500
+///
+/// [[drone]]
+/// # ... the nodes, as in config.toml
+///
+/// [[action]]
+/// at = 0
+/// send = { from = 4, to = 6, messages = 10, fragments = 2 }
+///
+/// [[action]]
+/// at = 10
+/// crash = 3
+///
+/// [[action]]
+/// at = 20
+/// set_pdr = { drone = 2, pdr = 0.4 }
+///
+/// [[action]]
+/// at = 30
+/// set_pdr_schedule = { drone = 1, schedule = { kind = "linear", from = 0.0, to = 0.5, start = 30, end = 80 } }
+///
+/// [expect]
+/// min_delivery_ratio = 0.8
+/// ```
+///
+/// The scenario runs on a `Simulation` of `RustDoIt` drones, so the time is virtual
+/// (one unit per hop) and two runs of the same scenario give the same report
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scenario {
+    /// Seed of the random number generators of the drones
+    #[serde(default)]
+    pub seed: u64,
+    /// Virtual time at which the run stops, even if packets are still travelling
+    #[serde(default = "default_duration")]
+    pub duration: u64,
+    #[serde(flatten)]
+    pub network: NetworkConfig,
+    #[serde(default, rename = "action")]
+    pub actions: Vec<TimedAction>,
+    #[serde(default)]
+    pub expect: Expectations,
+}
+
+/// An action of the timeline, executed at the beginning of round `at`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TimedAction {
+    pub at: u64,
+    #[serde(flatten)]
+    pub action: ScenarioAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScenarioAction {
+    /// Crashes a drone, after its neighbours removed it
+    Crash(NodeId),
+    SetPdr { drone: NodeId, pdr: f32 },
+    /// Makes the pdr of a drone follow a schedule of the virtual time
+    SetPdrSchedule { drone: NodeId, schedule: PdrSchedule },
+    Link(NodeId, NodeId),
+    Unlink(NodeId, NodeId),
+    /// Sends messages along the shortest route at the time of the action
+    Send {
+        from: NodeId,
+        to: NodeId,
+        #[serde(default = "one")]
+        messages: u64,
+        #[serde(default = "one")]
+        fragments: u64,
+    },
+}
+
+/// The outcome expected from the scenario. A panic always fails the scenario
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectations {
+    pub min_delivery_ratio: Option<f64>,
+    pub max_delivery_ratio: Option<f64>,
+    /// Maximum number of fragments dropped by the drones
+    pub max_dropped: Option<u64>,
+}
+
+/// A check of the report against an expectation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub description: String,
+    pub passed: bool,
+}
+
+/// What happened during the run of a scenario
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScenarioReport {
+    /// Fragments sent, including the ones of the messages without a route
+    pub fragments_sent: u64,
+    /// Fragments that reached the endpoint they were sent to
+    pub fragments_delivered: u64,
+    /// Fragments that the drones reported as dropped
+    pub fragments_dropped: u64,
+    /// Messages not sent because there was no route to their destination
+    pub messages_unroutable: u64,
+    /// Virtual time at which the run ended
+    pub end_time: u64,
+    /// The message of the panic that interrupted the run, if any
+    pub panic: Option<String>,
+    pub checks: Vec<Check>,
+}
+
+impl ScenarioReport {
+    /// Returns the ratio between the fragments delivered and the fragments sent
+    pub fn delivery_ratio(&self) -> f64 {
+        if self.fragments_sent == 0 {
+            0.0
+        } else {
+            self.fragments_delivered as f64 / self.fragments_sent as f64
+        }
+    }
+
+    /// Returns true if the run did not panic and every check passed
+    pub fn passed(&self) -> bool {
+        self.panic.is_none() && self.checks.iter().all(|check| check.passed)
+    }
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "delivered {}/{} fragments (ratio {:.3}), {} dropped, {} unroutable messages, ended at t={}",
+            self.fragments_delivered,
+            self.fragments_sent,
+            self.delivery_ratio(),
+            self.fragments_dropped,
+            self.messages_unroutable,
+            self.end_time
+        )?;
+        match &self.panic {
+            Some(message) => writeln!(f, "FAIL no panics: {}", message)?,
+            None => writeln!(f, "PASS no panics")?,
+        }
+        for check in self.checks.iter() {
+            writeln!(f, "{} {}", if check.passed { "PASS" } else { "FAIL" }, check.description)?;
+        }
+        write!(f, "result: {}", if self.passed() { "PASS" } else { "FAIL" })
+    }
+}
+
+impl Scenario {
+    /// Parses a scenario from its TOML representation
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    /// Runs the scenario and checks its expectations
+    pub fn run(&self) -> ScenarioReport {
+        let mut simulation = Simulation::from_config(&Config::from(&self.network), self.seed);
+        let mut report = ScenarioReport::default();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            self.execute(&mut simulation, &mut report);
+        }));
+        if let Err(payload) = result {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            report.panic = Some(message);
+        }
+
+        report.end_time = simulation.now();
+        report.fragments_delivered = simulation.deliveries()
+            .iter()
+            .filter(|delivery| matches!(delivery.packet.pack_type, PacketType::MsgFragment(_)))
+            .filter(|delivery| delivery.packet.routing_header.hops.last() == Some(&delivery.node))
+            .count() as u64;
+        report.fragments_dropped = simulation.events()
+            .iter()
+            .filter(|timed| matches!(&timed.event, DroneEvent::PacketDropped(packet) if matches!(packet.pack_type, PacketType::MsgFragment(_))))
+            .count() as u64;
+        report.checks = self.check(&report);
+        report
+    }
+
+    fn execute(&self, simulation: &mut Simulation, report: &mut ScenarioReport) {
+        // This function plays the timeline of the scenario on the simulation,
+        // then lets the packets travel until the network is idle
+        // ### Parameters:
+        // - `simulation`: The simulation to drive
+        // - `report`: The report in which the sent fragments are counted
+
+        let mut actions = self.actions.clone();
+        actions.sort_by_key(|action| action.at);
+        let mut session_id = 0;
+
+        for timed in actions.iter().filter(|timed| timed.at < self.duration) {
+            simulation.run_until(timed.at);
+
+            match &timed.action {
+                ScenarioAction::Crash(drone) => {
+                    for neighbour in simulation.neighbours(*drone) {
+                        if simulation.drone(neighbour).is_some() {
+                            simulation.send_command(neighbour, DroneCommand::RemoveSender(*drone));
+                        }
+                    }
+                    simulation.send_command(*drone, DroneCommand::Crash);
+                },
+                ScenarioAction::SetPdr { drone, pdr } => {
+                    simulation.send_command(*drone, DroneCommand::SetPacketDropRate(*pdr));
+                },
+                ScenarioAction::SetPdrSchedule { drone, schedule } => {
+                    if !simulation.configure_drone(*drone, |d| d.with_pdr_schedule(schedule.clone())) {
+                        warn!("Scenario could not set the pdr schedule of drone {}", drone);
+                    }
+                },
+                ScenarioAction::Link(a, b) => simulation.connect(*a, *b),
+                ScenarioAction::Unlink(a, b) => {
+                    for (drone, neighbour) in [(*a, *b), (*b, *a)] {
+                        if simulation.drone(drone).is_some() {
+                            simulation.send_command(drone, DroneCommand::RemoveSender(neighbour));
+                        }
+                    }
+                },
+                ScenarioAction::Send { from, to, messages, fragments } => {
+                    let Some(route) = simulation.route(*from, *to).filter(|route| route.len() > 2) else {
+                        warn!("Scenario found no route from {} to {} at t={}", from, to, timed.at);
+                        report.messages_unroutable += messages;
+                        report.fragments_sent += messages * fragments;
+                        continue;
+                    };
+                    for _ in 0..*messages {
+                        session_id += 1;
+                        for fragment_index in 0..*fragments {
+                            let packet = Packet::new_fragment(
+                                SourceRoutingHeader::new(route.clone(), 1),
+                                session_id,
+                                Fragment {
+                                    fragment_index,
+                                    total_n_fragments: *fragments,
+                                    length: 128,
+                                    data: [0; 128],
+                                },
+                            );
+                            simulation.inject(timed.at, route[1], packet);
+                            report.fragments_sent += 1;
+                        }
+                    }
+                },
+            }
+        }
+
+        simulation.run_until_idle(self.duration);
+    }
+
+    fn check(&self, report: &ScenarioReport) -> Vec<Check> {
+        let ratio = report.delivery_ratio();
+        let mut checks = Vec::new();
+
+        if let Some(min) = self.expect.min_delivery_ratio {
+            checks.push(Check {
+                description: format!("delivery ratio {:.3} >= {:.3}", ratio, min),
+                passed: ratio >= min,
+            });
+        }
+        if let Some(max) = self.expect.max_delivery_ratio {
+            checks.push(Check {
+                description: format!("delivery ratio {:.3} <= {:.3}", ratio, max),
+                passed: ratio <= max,
+            });
+        }
+        if let Some(max) = self.expect.max_dropped {
+            checks.push(Check {
+                description: format!("dropped fragments {} <= {}", report.fragments_dropped, max),
+                passed: report.fragments_dropped <= max,
+            });
+        }
+        checks
+    }
+}
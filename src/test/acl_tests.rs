@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeSet, HashMap};
+    use std::thread;
+    use std::time::Duration;
+    use crossbeam_channel::unbounded;
+    use wg_2024::controller::{DroneCommand, DroneEvent};
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Fragment, Nack, NackType, Packet, PacketType};
+
+    use crate::drone::RustDoIt;
+    use crate::drone::acl::{Acl, AclResponse, AclRule};
+    use crate::drone::command::RustDoItCommand;
+    use crate::drone::core::{Action, DroneCore};
+    use crate::drone::report::DroneReport;
+
+    fn fragment(hops: Vec<u8>) -> Packet {
+        Packet::new_fragment(
+            SourceRoutingHeader::new(hops, 1),
+            1,
+            Fragment {
+                fragment_index: 1,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            },
+        )
+    }
+
+    /// Denies the packets from client 2 and the packets to client 2
+    fn isolate_client_2(response: AclResponse) -> Acl {
+        Acl {
+            sources: AclRule::Deny(BTreeSet::from([2])),
+            destinations: AclRule::Deny(BTreeSet::from([2])),
+            response,
+        }
+    }
+
+    #[test]
+    /// A fragment from a denied source gets an ErrorInRouting nack naming the drone
+    fn acl_nack() {
+        let mut core = DroneCore::new(11, [1, 2, 12], 0.0).with_acl(isolate_client_2(AclResponse::Nack));
+
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 1,
+                nack_type: NackType::ErrorInRouting(11),
+            }),
+            routing_header: SourceRoutingHeader::new(vec![11, 2], 1),
+            session_id: 1,
+        };
+        assert_eq!(core.handle_packet(fragment(vec![2, 11, 12, 21])), vec![Action::Forward(2, nack)]);
+
+        // the other clients are not affected
+        let actions = core.handle_packet(fragment(vec![1, 11, 12, 21]));
+        assert!(matches!(&actions[..], [Action::Forward(12, _)]));
+        assert_eq!(core.stats().packets_denied(), 1);
+    }
+
+    #[test]
+    /// A denied ack is not lost: it is reported and given to the controller,
+    /// with either response
+    fn acl_denied_ack() {
+        let ack = Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 1 }),
+            routing_header: SourceRoutingHeader::new(vec![21, 12, 11, 2], 2),
+            session_id: 1,
+        };
+
+        for response in [AclResponse::Nack, AclResponse::Report] {
+            let mut core = DroneCore::new(11, [2, 12], 0.0).with_acl(isolate_client_2(response));
+            assert_eq!(core.handle_packet(ack.clone()), vec![
+                Action::Report(DroneReport::PacketDenied {
+                    drone: 11,
+                    session_id: 1,
+                    source: 21,
+                    destination: 2,
+                }),
+                Action::Event(DroneEvent::ControllerShortcut(ack.clone())),
+            ]);
+            assert_eq!(core.stats().packets_denied(), 1);
+        }
+    }
+
+    #[test]
+    /// With `AclResponse::Report` the denied packet is reported to the controller
+    fn acl_report() {
+        let acl = Acl {
+            destinations: AclRule::Allow(BTreeSet::from([21])),
+            response: AclResponse::Report,
+            ..Default::default()
+        };
+        let mut core = DroneCore::new(11, [1, 12], 0.0).with_acl(acl);
+
+        let msg = fragment(vec![1, 11, 12, 22]);
+        assert_eq!(core.handle_packet(msg.clone()), vec![
+            Action::Report(DroneReport::PacketDenied {
+                drone: 11,
+                session_id: 1,
+                source: 1,
+                destination: 22,
+            }),
+            Action::Drop(msg),
+        ]);
+    }
+
+    #[test]
+    /// A running drone sends the denied packets on its report channel
+    fn acl_report_while_running() {
+        let (controller_send, _event_recv) = unbounded();
+        let (command_send, controller_recv) = unbounded();
+        let (packet_send, packet_recv) = unbounded();
+        let (report_send, report_recv) = unbounded();
+        let (d_send, d_recv) = unbounded();
+
+        let mut drone = RustDoIt::new(
+            11,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            HashMap::from([(12, d_send)]),
+            0.0,
+        ).with_acl(isolate_client_2(AclResponse::Report)).with_reports(report_send);
+        let handle = thread::spawn(move || drone.run());
+
+        packet_send.send(fragment(vec![2, 11, 12, 21])).unwrap();
+        assert_eq!(report_recv.recv_timeout(Duration::from_secs(1)), Ok(DroneReport::PacketDenied {
+            drone: 11,
+            session_id: 1,
+            source: 2,
+            destination: 21,
+        }));
+        assert!(d_recv.try_recv().is_err());
+
+        command_send.send(DroneCommand::Crash).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    /// The ACL of a running drone is replaced through the extended command channel
+    fn acl_set_at_runtime() {
+        let (controller_send, _event_recv) = unbounded();
+        let (command_send, controller_recv) = unbounded();
+        let (packet_send, packet_recv) = unbounded();
+        let (extended_send, extended_recv) = unbounded();
+        let (c_send, c_recv) = unbounded();
+        let (d_send, d_recv) = unbounded();
+
+        let mut drone = RustDoIt::new(
+            11,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            HashMap::from([(2, c_send), (12, d_send)]),
+            0.0,
+        ).with_extended_commands(extended_recv);
+        let handle = thread::spawn(move || drone.run());
+
+        packet_send.send(fragment(vec![2, 11, 12, 21])).unwrap();
+        assert!(d_recv.recv_timeout(Duration::from_secs(1)).is_ok());
+
+        // the extended commands are selected before the packets, so the command
+        // is applied before the fragment sent after it is handled
+        extended_send.send(RustDoItCommand::SetAcl(isolate_client_2(AclResponse::Nack))).unwrap();
+        packet_send.send(fragment(vec![2, 11, 12, 21])).unwrap();
+
+        let nack = c_recv.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(matches!(nack.pack_type, PacketType::Nack(Nack { nack_type: NackType::ErrorInRouting(11), .. })));
+        assert!(d_recv.try_recv().is_err());
+
+        command_send.send(DroneCommand::Crash).unwrap();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(all(test, feature = "async"))]
+mod test {
+    use async_channel::unbounded;
+    use futures_lite::future;
+    use std::collections::HashMap;
+    use wg_2024::controller::{DroneCommand, DroneEvent};
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Fragment, Packet, PacketType};
+
+    use crate::drone::async_drone::AsyncRustDoIt;
+
+    fn create_sample_packet() -> Packet {
+        Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index: 1,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 12, 21],
+            },
+            session_id: 1,
+        }
+    }
+
+    #[test]
+    /// Test forward of a fragment through a chain of two async drones
+    fn async_chain_forward() {
+        let (c_send, _c_recv) = unbounded();
+        let (s_send, s_recv) = unbounded();
+        let (d11_send, d11_recv) = unbounded();
+        let (d12_send, d12_recv) = unbounded();
+        let (d_command_send, d_command_recv) = unbounded();
+        let (d_event_send, d_event_recv) = unbounded();
+
+        let mut drone11 = AsyncRustDoIt::new(
+            11,
+            d_event_send.clone(),
+            d_command_recv.clone(),
+            d11_recv,
+            HashMap::from([(1, c_send), (12, d12_send)]),
+            0.0,
+        );
+        let mut drone12 = AsyncRustDoIt::new(
+            12,
+            d_event_send,
+            d_command_recv,
+            d12_recv,
+            HashMap::from([(11, d11_send.clone()), (21, s_send)]),
+            0.0,
+        );
+
+        let msg = create_sample_packet();
+        let got = future::block_on(async {
+            let drones = future::zip(drone11.run(), drone12.run());
+            let test = async {
+                d11_send.send(msg.clone()).await.unwrap();
+                let got = s_recv.recv().await.unwrap();
+                // one crash for each drone
+                d_command_send.send(DroneCommand::Crash).await.unwrap();
+                d_command_send.send(DroneCommand::Crash).await.unwrap();
+                got
+            };
+            future::zip(drones, test).await.1
+        });
+
+        let mut expected = msg;
+        expected.routing_header.hop_index = 3;
+        assert_eq!(got, expected);
+
+        let events = std::iter::from_fn(|| d_event_recv.try_recv().ok()).collect::<Vec<_>>();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1], DroneEvent::PacketSent(expected));
+    }
+
+    #[test]
+    /// Checks that the packets waiting in the channel are handled when the async drone crashes
+    fn async_crash_drains_packets() {
+        let (c_send, _c_recv) = unbounded();
+        let (d12_send, d12_recv) = unbounded();
+        let (d11_send, d11_recv) = unbounded();
+        let (d_command_send, d_command_recv) = unbounded();
+        let (d_event_send, d_event_recv) = unbounded();
+
+        let mut drone = AsyncRustDoIt::new(
+            11,
+            d_event_send,
+            d_command_recv,
+            d11_recv,
+            HashMap::from([(1, c_send), (12, d12_send)]),
+            0.0,
+        );
+
+        let mut ack = Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 1 }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 12],
+            },
+            session_id: 1,
+        };
+        future::block_on(async {
+            d11_send.send(ack.clone()).await.unwrap();
+            d_command_send.send(DroneCommand::Crash).await.unwrap();
+            drone.run().await;
+        });
+
+        ack.routing_header.hop_index += 1;
+        assert_eq!(d12_recv.try_recv().unwrap(), ack);
+        assert_eq!(d_event_recv.try_recv().unwrap(), DroneEvent::PacketSent(ack));
+    }
+}
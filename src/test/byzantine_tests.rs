@@ -0,0 +1,107 @@
+#[cfg(all(test, feature = "byzantine"))]
+mod test {
+    use std::collections::BTreeSet;
+    use std::num::NonZeroUsize;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{FloodRequest, Fragment, NackType, NodeType, Packet, PacketType};
+
+    use crate::drone::byzantine::ByzantineMode;
+    use crate::drone::core::{Action, DroneCore};
+
+    fn fragment(session_id: u64) -> Packet {
+        Packet::new_fragment(
+            SourceRoutingHeader::new(vec![1, 11, 12, 21], 1),
+            session_id,
+            Fragment {
+                fragment_index: 3,
+                total_n_fragments: 4,
+                length: 128,
+                data: [1; 128],
+            },
+        )
+    }
+
+    fn flood_request() -> Packet {
+        Packet::new_flood_request(
+            SourceRoutingHeader::new(vec![], 0),
+            1,
+            FloodRequest {
+                flood_id: 1,
+                initiator_id: 1,
+                path_trace: vec![(1, NodeType::Client)],
+            },
+        )
+    }
+
+    fn drone(mode: ByzantineMode) -> DroneCore {
+        DroneCore::new(11, [1, 12, 13], 0.0).with_byzantine_mode(mode)
+    }
+
+    #[test]
+    /// A black hole swallows every fragment but relays the other packets,
+    /// a grey hole only swallows the listed sessions
+    fn black_and_grey_holes() {
+        let mut black_hole = drone(ByzantineMode::BlackHole);
+        assert!(matches!(black_hole.handle_packet(fragment(1))[..], [Action::Drop(_)]));
+        let ack = Packet::new_ack(SourceRoutingHeader::new(vec![21, 12, 11, 1], 2), 1, 3);
+        assert!(matches!(black_hole.handle_packet(ack)[..], [Action::Forward(1, _)]));
+
+        let mut grey_hole = drone(ByzantineMode::GreyHole(BTreeSet::from([2])));
+        assert!(matches!(grey_hole.handle_packet(fragment(1))[..], [Action::Forward(12, _)]));
+        assert!(matches!(grey_hole.handle_packet(fragment(2))[..], [Action::Drop(_)]));
+        assert_eq!(grey_hole.byzantine_mode(), Some(&ByzantineMode::GreyHole(BTreeSet::from([2]))));
+    }
+
+    #[test]
+    /// The tamperer replaces the rest of the route, the packet follows the new one
+    fn route_tamperer() {
+        let mut drone = drone(ByzantineMode::RouteTamperer(vec![13, 22]));
+        let actions = drone.handle_packet(fragment(1));
+        let [Action::Forward(13, packet)] = &actions[..] else {
+            panic!("unexpected actions {:?}", actions);
+        };
+        assert_eq!(packet.routing_header.hops, vec![1, 11, 13, 22]);
+        assert_eq!(packet.routing_header.hop_index, 2);
+    }
+
+    #[test]
+    /// The fragment is forwarded, and a fake nack goes back to the source
+    fn fake_nack() {
+        let mut drone = drone(ByzantineMode::FakeNack(NackType::Dropped));
+        let actions = drone.handle_packet(fragment(1));
+        let [Action::Forward(12, _), Action::Forward(1, nack)] = &actions[..] else {
+            panic!("unexpected actions {:?}", actions);
+        };
+        assert_eq!(nack.routing_header.hops, vec![11, 1]);
+        assert!(matches!(
+            &nack.pack_type,
+            PacketType::Nack(nack) if nack.fragment_index == 3 && nack.nack_type == NackType::Dropped
+        ));
+    }
+
+    #[test]
+    /// The amplifier sends each flood request several times, the forger
+    /// adds fake nodes before itself in the path trace
+    fn flood_misbehaviours() {
+        let mut amplifier = drone(ByzantineMode::FloodAmplifier(NonZeroUsize::new(3).unwrap()));
+        let next_hops = amplifier
+            .handle_packet(flood_request())
+            .iter()
+            .map(|action| match action {
+                Action::Forward(next_hop, _) => *next_hop,
+                action => panic!("unexpected action {:?}", action),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(next_hops, vec![12, 12, 12, 13, 13, 13]);
+
+        let mut forger = drone(ByzantineMode::PathTraceForger(vec![(40, NodeType::Drone)]));
+        let actions = forger.handle_packet(flood_request());
+        let Action::Forward(_, Packet { pack_type: PacketType::FloodRequest(request), .. }) = &actions[0] else {
+            panic!("unexpected actions {:?}", actions);
+        };
+        assert_eq!(
+            request.path_trace,
+            vec![(1, NodeType::Client), (40, NodeType::Drone), (11, NodeType::Drone)]
+        );
+    }
+}
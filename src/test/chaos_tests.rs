@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use crate::network::{ChaosAction, ChaosConfig, ChaosConfigError, ChaosEngine, DroneRegistry, Network, NetworkConfig};
+
+    // A ring of five drones, with a client and a server attached to two of them each
+    const RING: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [12, 15, 1]
+        pdr = 0.0
+
+        [[drone]]
+        id = 12
+        connected_node_ids = [11, 13, 1]
+        pdr = 0.0
+
+        [[drone]]
+        id = 13
+        connected_node_ids = [12, 14, 21]
+        pdr = 0.0
+
+        [[drone]]
+        id = 14
+        connected_node_ids = [13, 15, 21]
+        pdr = 0.0
+
+        [[drone]]
+        id = 15
+        connected_node_ids = [14, 11]
+        pdr = 0.0
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11, 12]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [13, 14]
+    "#;
+
+    fn network() -> Network {
+        let config = NetworkConfig::from_toml(RING).unwrap();
+        Network::start(&config, &DroneRegistry::default()).unwrap()
+    }
+
+    fn links(network: &Network) -> Vec<(u8, BTreeSet<u8>)> {
+        network.nodes().map(|id| (id, network.neighbours(id))).collect()
+    }
+
+    #[test]
+    /// The same seed gives the same actions, and replaying them gives the same topology
+    fn deterministic_and_replayable() {
+        let mut first = network();
+        let mut second = network();
+        let actions = ChaosEngine::new(42).run(&mut first, 30);
+        assert_eq!(actions.len(), 30);
+        assert_eq!(actions, ChaosEngine::new(42).run(&mut second, 30));
+
+        let mut replayed = network();
+        let log = actions.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let parsed = log.iter().map(|line| line.parse().unwrap()).collect::<Vec<ChaosAction>>();
+        assert_eq!(parsed, actions);
+        crate::network::chaos::replay(&mut replayed, &parsed);
+
+        assert_eq!(links(&replayed), links(&first));
+        for drone in first.drones().collect::<Vec<_>>() {
+            assert_eq!(replayed.is_crashed(drone), first.is_crashed(drone));
+        }
+        first.shutdown();
+        second.shutdown();
+        replayed.shutdown();
+    }
+
+    #[test]
+    /// After every action the client still reaches the server through running drones
+    fn constraints_hold() {
+        let mut network = network();
+        let mut engine = ChaosEngine::new(7).with_config(ChaosConfig {
+            crash_weight: 5,
+            max_crashes: Some(2),
+            ..ChaosConfig::default()
+        }).unwrap();
+
+        for _ in 0..100 {
+            engine.step(&mut network).unwrap();
+            assert!(network.route(1, 21).is_some(), "{:?}", engine.log());
+            assert!(network.neighbours(1).iter().any(|drone| !network.is_crashed(*drone)));
+        }
+        let crashes = engine.log().iter().filter(|action| matches!(action, ChaosAction::Crash(_))).count();
+        assert_eq!(crashes, 2);
+        assert!(engine.log().iter().all(|action| match action {
+            ChaosAction::SetPdr(_, pdr) => (0.0..=0.5).contains(pdr),
+            _ => true,
+        }));
+        network.shutdown();
+    }
+
+    #[test]
+    /// Packet drop rates the drones would refuse cannot be the highest one
+    fn invalid_config() {
+        for max_pdr in [-0.1, 1.5, f32::NAN] {
+            let config = ChaosConfig { max_pdr, ..ChaosConfig::default() };
+            assert!(matches!(ChaosEngine::new(7).with_config(config), Err(ChaosConfigError::InvalidMaxPdr(_))));
+        }
+        assert!(ChaosEngine::new(7).with_config(ChaosConfig { max_pdr: 1.0, ..ChaosConfig::default() }).is_ok());
+    }
+}
@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+    use wg_2024::drone::Drone;
+    use wg_2024::network::{NodeId, SourceRoutingHeader};
+    use wg_2024::packet::{Fragment, Packet, PacketType};
+
+    use crate::drone::core::{Action, DroneCore};
+    use crate::network::checksum::{crc32, open, seal, ChecksumError};
+    use crate::network::endpoint::MAX_OUTGOING;
+    use crate::network::{DroneChannels, DroneRegistry, EndpointEvent, Endpoints, Message, Network, NetworkConfig};
+    use crate::RustDoIt;
+
+    const TOPOLOGY: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [1, 21]
+        pdr = 0.0
+        implementation = "noisy"
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [11]
+    "#;
+
+    fn fragment(length: u8) -> Packet {
+        Packet::new_fragment(
+            SourceRoutingHeader::new(vec![1, 11, 21], 1),
+            1,
+            Fragment {
+                fragment_index: 0,
+                total_n_fragments: 1,
+                length,
+                data: [0; 128],
+            },
+        )
+    }
+
+    fn forwarded_data(actions: &[Action]) -> [u8; 128] {
+        match actions {
+            [Action::Forward(_, Packet { pack_type: PacketType::MsgFragment(fragment), .. })] => fragment.data,
+            _ => panic!("unexpected actions {:?}", actions),
+        }
+    }
+
+    /// A `RustDoIt` flipping every bit of the fragments it forwards
+    fn noisy(id: NodeId, channels: DroneChannels, pdr: f32) -> Box<dyn FnOnce() + Send> {
+        Box::new(move || {
+            RustDoIt::new(
+                id,
+                channels.controller_send,
+                channels.controller_recv,
+                channels.packet_recv,
+                channels.packet_send,
+                pdr,
+            ).with_bit_error_rate(1.0).run();
+        })
+    }
+
+    /// A `RustDoIt` flipping few bits, seeded so that it corrupts the first fragment
+    /// it forwards and leaves the second one intact
+    fn flaky(id: NodeId, channels: DroneChannels, pdr: f32) -> Box<dyn FnOnce() + Send> {
+        Box::new(move || {
+            RustDoIt::new(
+                id,
+                channels.controller_send,
+                channels.controller_recv,
+                channels.packet_recv,
+                channels.packet_send,
+                pdr,
+            ).with_bit_error_rate(0.01).with_seed(3).run();
+        })
+    }
+
+    /// Polls the endpoints until an event matches or a second has passed, returning every event
+    fn collect_until(network: &Network, endpoints: &mut Endpoints, done: impl Fn(&EndpointEvent) -> bool) -> Vec<EndpointEvent> {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut events = Vec::new();
+        loop {
+            endpoints.handle_events(network, &network.events());
+            events.extend(endpoints.poll(network));
+            if events.iter().any(&done) || !network.wait(deadline) {
+                return events;
+            }
+        }
+    }
+
+    #[test]
+    /// The checksum of a sealed message is verified, a single flipped bit is detected
+    fn seal_and_open() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+
+        let mut sealed = seal(b"hello");
+        assert_eq!(sealed.len(), 9);
+        assert_eq!(open(&sealed), Ok(&b"hello"[..]));
+
+        sealed[1] ^= 0x10;
+        assert!(matches!(open(&sealed), Err(ChecksumError::Mismatch { .. })));
+        assert_eq!(open(&[1, 2, 3]), Err(ChecksumError::TooShort));
+    }
+
+    #[test]
+    /// The bit error rate flips the bits of the payload of the forwarded fragments, within their length
+    fn drone_flips_bits() {
+        let mut clean = DroneCore::new(11, [1, 21], 0.0);
+        assert_eq!(forwarded_data(&clean.handle_packet(fragment(10))), [0; 128]);
+
+        let mut noisy = DroneCore::new(11, [1, 21], 0.0).with_bit_error_rate(1.0);
+        let data = forwarded_data(&noisy.handle_packet(fragment(10)));
+        assert!(data[..10].iter().all(|byte| *byte == 0xFF));
+        assert!(data[10..].iter().all(|byte| *byte == 0));
+        assert_eq!(noisy.stats().bits_flipped(), 80);
+
+        let mut broken = DroneCore::new(11, [1, 21], 0.0).with_bit_error_rate(f64::NAN);
+        assert_eq!(broken.bit_error_rate(), 0.0);
+        assert_eq!(forwarded_data(&broken.handle_packet(fragment(10))), [0; 128]);
+
+        let mut seeded = DroneCore::new(11, [1, 21], 0.0).with_bit_error_rate(0.01).with_seed(3);
+        let flipped = forwarded_data(&seeded.handle_packet(fragment(128)))
+            .iter()
+            .map(|byte| byte.count_ones())
+            .sum::<u32>();
+        assert_eq!(u64::from(flipped), seeded.stats().bits_flipped());
+        assert!((1..40).contains(&flipped));
+    }
+
+    #[test]
+    /// A message corrupted on every attempt is sent again until it runs out of retransmissions
+    fn corrupted_message_is_retransmitted() {
+        let mut registry = DroneRegistry::empty();
+        registry.register_factory("noisy", noisy);
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let network = Network::start(&config, &registry).unwrap();
+        let mut endpoints = Endpoints::new(&network).with_checksums(2);
+
+        let session_id = endpoints.send(&network, 1, 21, b"hello").unwrap();
+        let events = collect_until(&network, &mut endpoints, |event| {
+            matches!(event, EndpointEvent::Corrupted { retransmitted: false, .. })
+        });
+
+        let corrupted = events
+            .iter()
+            .filter_map(|event| match event {
+                EndpointEvent::Corrupted { from: 1, to: 21, session_id: id, retransmitted } if *id == session_id => {
+                    Some(*retransmitted)
+                },
+                EndpointEvent::Delivered(message) => panic!("delivered corrupted message {:?}", message),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(corrupted, vec![true, true, false]);
+
+        let stats = endpoints.stats();
+        assert_eq!((stats.messages_corrupted, stats.retransmissions, stats.messages_delivered), (3, 2, 0));
+        assert_eq!((stats.fragments_sent, stats.fragments_delivered), (3, 0));
+        network.shutdown();
+    }
+
+    #[test]
+    /// A message corrupted once is sent again and delivered intact, and is no longer kept by its source
+    fn retransmission_delivered() {
+        let mut registry = DroneRegistry::empty();
+        registry.register_factory("noisy", flaky);
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let network = Network::start(&config, &registry).unwrap();
+        let mut endpoints = Endpoints::new(&network).with_checksums(2);
+
+        let session_id = endpoints.send(&network, 1, 21, b"hello").unwrap();
+        let events = collect_until(&network, &mut endpoints, |event| matches!(event, EndpointEvent::Delivered(_)));
+
+        assert!(events.contains(&EndpointEvent::Corrupted { from: 1, to: 21, session_id, retransmitted: true }));
+        assert!(events.contains(&EndpointEvent::Delivered(Message { from: 1, to: 21, session_id, data: b"hello".to_vec() })));
+        let stats = endpoints.stats();
+        assert_eq!((stats.messages_corrupted, stats.retransmissions, stats.messages_delivered), (1, 1, 1));
+        assert_eq!((stats.fragments_sent, stats.fragments_delivered), (2, 1));
+        assert_eq!(endpoints.pending(), 0);
+        network.shutdown();
+    }
+
+    #[test]
+    /// The source keeps at most `MAX_OUTGOING` messages waiting for their delivery
+    fn pending_messages_bounded() {
+        let mut registry = DroneRegistry::empty();
+        registry.register_factory("noisy", noisy);
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let network = Network::start(&config, &registry).unwrap();
+        let mut endpoints = Endpoints::new(&network).with_checksums(2);
+
+        for _ in 0..=MAX_OUTGOING {
+            endpoints.send(&network, 1, 21, b"hello").unwrap();
+        }
+        assert_eq!(endpoints.pending(), MAX_OUTGOING);
+        network.shutdown();
+    }
+}
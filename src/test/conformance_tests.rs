@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use crossbeam_channel::{Receiver, Sender};
+    use wg_2024::controller::{DroneCommand, DroneEvent};
+    use wg_2024::drone::Drone;
+    use wg_2024::network::NodeId;
+    use wg_2024::packet::Packet;
+
+    use crate::conformance;
+    use crate::drone::RustDoIt;
+
+    /// A drone that accepts every packet and never sends anything
+    struct SilentDrone {
+        controller_recv: Receiver<DroneCommand>,
+    }
+
+    impl Drone for SilentDrone {
+        fn new(
+            _id: NodeId,
+            _controller_send: Sender<DroneEvent>,
+            controller_recv: Receiver<DroneCommand>,
+            _packet_recv: Receiver<Packet>,
+            _packet_send: HashMap<NodeId, Sender<Packet>>,
+            _pdr: f32,
+        ) -> Self {
+            Self { controller_recv }
+        }
+
+        fn run(&mut self) {
+            // wait for the crash, leaving the packets in the channel
+            self.controller_recv.recv().ok();
+        }
+    }
+
+    #[test]
+    /// RustDoIt passes the whole conformance battery
+    fn rust_do_it_conformance() {
+        let report = conformance::run_all::<RustDoIt>();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.results.len(), conformance::checks::<RustDoIt>().len());
+    }
+
+    #[test]
+    /// A drone that never forwards anything fails every check
+    fn silent_drone_conformance() {
+        let report = conformance::run_all::<SilentDrone>();
+        assert_eq!(report.failures().len(), report.results.len());
+    }
+}
@@ -0,0 +1,187 @@
+#[cfg(test)]
+mod test {
+    use wg_2024::controller::{DroneCommand, DroneEvent};
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Fragment, Nack, NackType, NodeType, Packet, PacketType};
+
+    use crate::drone::core::{Action, DroneCore};
+
+    fn create_sample_packet() -> Packet {
+        Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index: 1,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 12, 21],
+            },
+            session_id: 1,
+        }
+    }
+
+    #[test]
+    /// Checks that a fragment is forwarded to the next hop with the hop index increased
+    fn core_forward() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0);
+        let msg = create_sample_packet();
+
+        let mut expected = msg.clone();
+        expected.routing_header.hop_index += 1;
+
+        assert_eq!(core.handle_packet(msg), vec![Action::Forward(12, expected)]);
+    }
+
+    #[test]
+    /// Checks that a dropped fragment is reported to the controller before the nack is sent back
+    fn core_drop() {
+        let mut core = DroneCore::new(11, [1, 12], 1.0);
+        let msg = create_sample_packet();
+
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 1,
+                nack_type: NackType::Dropped,
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![11, 1],
+            },
+            session_id: 1,
+        };
+
+        assert_eq!(core.handle_packet(msg.clone()), vec![
+            Action::Event(DroneEvent::PacketDropped(msg)),
+            Action::Forward(1, nack),
+        ]);
+    }
+
+    #[test]
+    /// Checks that an ack is sent to the controller once the next hop is removed
+    fn core_shortcut_after_remove_sender() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0);
+        let ack = Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 1 }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![1, 11, 12],
+            },
+            session_id: 1,
+        };
+
+        assert!(core.handle_command(&DroneCommand::RemoveSender(12)).is_empty());
+
+        let mut expected = ack.clone();
+        expected.routing_header.hop_index += 1;
+        assert_eq!(
+            core.handle_packet(ack),
+            vec![Action::Event(DroneEvent::ControllerShortcut(expected))]
+        );
+    }
+
+    #[test]
+    /// Checks that a flood request is forwarded to every neighbour except the previous hop
+    fn core_flood_request() {
+        let mut core = DroneCore::new(11, [1, 12, 13], 0.0);
+        let flood_request = Packet::new_flood_request(
+            SourceRoutingHeader::new(vec![], 0),
+            7,
+            wg_2024::packet::FloodRequest {
+                flood_id: 3,
+                initiator_id: 1,
+                path_trace: vec![(1, NodeType::Client)],
+            },
+        );
+
+        let expected = Packet::new_flood_request(
+            SourceRoutingHeader::new(vec![], 0),
+            7,
+            wg_2024::packet::FloodRequest {
+                flood_id: 3,
+                initiator_id: 1,
+                path_trace: vec![(1, NodeType::Client), (11, NodeType::Drone)],
+            },
+        );
+
+        assert_eq!(core.handle_packet(flood_request.clone()), vec![
+            Action::Forward(12, expected.clone()),
+            Action::Forward(13, expected),
+        ]);
+
+        // the second time the same flood is seen, a response goes back to the previous hop
+        let actions = core.handle_packet(flood_request);
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(
+            &actions[0],
+            Action::Forward(1, Packet { pack_type: PacketType::FloodResponse(_), .. })
+        ));
+    }
+
+    #[test]
+    /// A fragment with no hops is dropped instead of panicking the drone
+    fn core_empty_hops() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0);
+        let mut msg = create_sample_packet();
+        msg.routing_header = SourceRoutingHeader::new(vec![], 0);
+
+        assert_eq!(core.handle_packet(msg.clone()), vec![Action::Drop(msg)]);
+    }
+
+    #[test]
+    /// A fragment whose hop index is past the end of the hops is dropped
+    fn core_hop_index_out_of_range() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0);
+        let mut msg = create_sample_packet();
+        msg.routing_header.hop_index = usize::MAX;
+
+        let actions = core.handle_packet(msg);
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(&actions[0], Action::Drop(_)));
+    }
+
+    #[test]
+    /// A crashing drone sends back an ErrorInRouting nack for its pending fragments
+    fn core_crash_fragment() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0);
+        let msg = create_sample_packet();
+
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 1,
+                nack_type: NackType::ErrorInRouting(11),
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![11, 1],
+            },
+            session_id: 1,
+        };
+
+        assert_eq!(core.handle_packet_crash(msg.clone()), vec![
+            Action::Forward(1, nack),
+            Action::Event(DroneEvent::PacketDropped(msg)),
+        ]);
+    }
+
+    #[test]
+    /// A crashing drone drops its pending flood requests and tells the controller
+    fn core_crash_flood_request() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0);
+        let flood_request = Packet::new_flood_request(
+            SourceRoutingHeader::new(vec![], 0),
+            7,
+            wg_2024::packet::FloodRequest {
+                flood_id: 3,
+                initiator_id: 1,
+                path_trace: vec![(1, NodeType::Client)],
+            },
+        );
+
+        assert_eq!(
+            core.handle_packet_crash(flood_request.clone()),
+            vec![Action::Event(DroneEvent::PacketDropped(flood_request))]
+        );
+    }
+}
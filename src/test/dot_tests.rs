@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod test {
+    use wg_2024::controller::{DroneCommand, DroneEvent};
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::Packet;
+
+    use crate::network::{DotGraph, DroneRegistry, Network, NetworkConfig};
+
+    const TOPOLOGY: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [1, 12]
+        pdr = 0.1
+
+        [[drone]]
+        id = 12
+        connected_node_ids = [11, 21]
+        pdr = 0.25
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [12]
+    "#;
+
+    #[test]
+    /// Nodes are coloured by type, drones are labelled with their pdr
+    /// and every link is drawn once
+    fn config_graph() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let dot = DotGraph::from_config(&config).render();
+
+        assert_eq!(dot, "\
+graph network {
+    node [style=filled];
+    1 [label=\"1\\nclient\", shape=box, fillcolor=palegreen];
+    11 [label=\"11\\npdr 0.10\", shape=ellipse, fillcolor=lightblue];
+    12 [label=\"12\\npdr 0.25\", shape=ellipse, fillcolor=lightblue];
+    21 [label=\"21\\nserver\", shape=box, fillcolor=orange];
+    1 -- 11;
+    11 -- 12;
+    12 -- 21;
+}
+");
+    }
+
+    #[test]
+    /// The route of a header is highlighted, a hop missing from the topology
+    /// is dashed, and the sent packets are counted on their link
+    fn path_and_traffic() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let ack = Packet::new_ack(SourceRoutingHeader::new(vec![21, 12, 11, 1], 2), 1, 0);
+        let events = [
+            DroneEvent::PacketSent(ack.clone()),
+            DroneEvent::PacketSent(ack.clone()),
+            DroneEvent::PacketDropped(ack),
+        ];
+        let dot = DotGraph::from_config(&config)
+            .with_path(&SourceRoutingHeader::new(vec![1, 11, 21], 1))
+            .with_events(events.iter())
+            .with_traffic([((21, 12), 5)])
+            .render();
+
+        assert!(dot.contains("    11 [label=\"11\\npdr 0.10\", shape=ellipse, fillcolor=lightblue, color=red, penwidth=2];\n"));
+        assert!(dot.contains("    12 [label=\"12\\npdr 0.25\", shape=ellipse, fillcolor=lightblue];\n"));
+        assert!(dot.contains("    1 -- 11 [color=red, penwidth=2];\n"));
+        assert!(dot.contains("    11 -- 12 [label=\"2\"];\n"));
+        assert!(dot.contains("    11 -- 21 [color=red, penwidth=2, style=dashed];\n"));
+        assert!(dot.contains("    12 -- 21 [label=\"5\"];\n"));
+    }
+
+    #[test]
+    /// The graph of a running network follows the commands sent to its drones
+    fn live_network_graph() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let mut network = Network::start(&config, &DroneRegistry::default()).unwrap();
+
+        assert!(network.send_command(11, DroneCommand::SetPacketDropRate(0.5)));
+        assert!(network.send_command(11, DroneCommand::RemoveSender(12)));
+        assert!(network.send_command(12, DroneCommand::Crash));
+        let dot = DotGraph::from_network(&network).render();
+
+        assert!(dot.contains("    11 [label=\"11\\npdr 0.50\""));
+        assert!(dot.contains("    12 [label=\"12\\ncrashed\", shape=ellipse, fillcolor=grey];\n"));
+        assert!(dot.contains("    1 -- 11;\n"));
+        assert!(!dot.contains("11 -- 12"));
+        network.shutdown();
+    }
+}
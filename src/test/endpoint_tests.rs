@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, NodeType, Packet};
+
+    use crate::network::endpoint::MAX_FRAGMENTS;
+    use crate::network::{DroneRegistry, EndpointEvent, Endpoints, Message, Network, NetworkConfig};
+
+    const TOPOLOGY: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [1, 12]
+        pdr = 0.0
+
+        [[drone]]
+        id = 12
+        connected_node_ids = [11, 21]
+        pdr = 0.0
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [12]
+    "#;
+
+    /// Polls the endpoints until `count` events have been collected or a second has passed
+    fn collect(network: &Network, endpoints: &mut Endpoints, count: usize) -> Vec<EndpointEvent> {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut events = Vec::new();
+        loop {
+            endpoints.handle_events(network, &network.events());
+            events.extend(endpoints.poll(network));
+            if events.len() >= count || !network.wait(deadline) {
+                return events;
+            }
+        }
+    }
+
+    #[test]
+    /// A message of several fragments is reassembled by the server,
+    /// which acknowledges every fragment
+    fn send_message() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let network = Network::start(&config, &DroneRegistry::default()).unwrap();
+        let mut endpoints = Endpoints::new(&network);
+
+        let data = (0..200).map(|n| n as u8).collect::<Vec<_>>();
+        let session_id = endpoints.send(&network, 1, 21, &data).unwrap();
+        let events = collect(&network, &mut endpoints, 3);
+
+        assert!(events.contains(&EndpointEvent::Delivered(Message { from: 1, to: 21, session_id, data })));
+        assert!(events.contains(&EndpointEvent::Acked { node: 1, session_id, fragment_index: 0 }));
+        assert!(events.contains(&EndpointEvent::Acked { node: 1, session_id, fragment_index: 1 }));
+
+        let stats = endpoints.stats();
+        assert_eq!((stats.fragments_sent, stats.fragments_delivered, stats.acks_received), (2, 2, 2));
+        assert_eq!(stats.delivery_ratio(), 1.0);
+        network.shutdown();
+    }
+
+    #[test]
+    /// A flood started by the client is answered by the drones and by the server
+    fn flood_from_client() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let network = Network::start(&config, &DroneRegistry::default()).unwrap();
+        let mut endpoints = Endpoints::new(&network);
+
+        let flood_id = endpoints.flood(&network, 1).unwrap();
+        let events = collect(&network, &mut endpoints, 1);
+
+        assert_eq!(events, vec![EndpointEvent::FloodResponse {
+            node: 1,
+            flood_id,
+            path_trace: vec![
+                (1, NodeType::Client),
+                (11, NodeType::Drone),
+                (12, NodeType::Drone),
+                (21, NodeType::Server),
+            ],
+        }]);
+        network.shutdown();
+    }
+
+    #[test]
+    /// Drones are not endpoints, and messages to unreachable nodes are not sent
+    fn unroutable_message() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let network = Network::start(&config, &DroneRegistry::default()).unwrap();
+        let mut endpoints = Endpoints::new(&network);
+
+        assert_eq!(endpoints.send(&network, 11, 21, b"hello"), None);
+        assert_eq!(endpoints.send(&network, 1, 30, b"hello"), None);
+        assert_eq!(endpoints.flood(&network, 12), None);
+        assert_eq!(endpoints.stats().messages_unroutable, 1);
+        network.shutdown();
+    }
+
+    #[test]
+    /// Fragments of no message, of a message too large, beyond the end of their message,
+    /// or disagreeing with the fragments already received are refused and not acknowledged.
+    /// A duplicate fragment is acknowledged but delivered once
+    fn malformed_fragments() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let network = Network::start(&config, &DroneRegistry::default()).unwrap();
+        let mut endpoints = Endpoints::new(&network);
+        let fragment = |session_id, fragment_index, total_n_fragments| {
+            Packet::new_fragment(
+                SourceRoutingHeader::new(vec![1, 11, 12, 21], 3),
+                session_id,
+                Fragment { fragment_index, total_n_fragments, length: 1, data: [7; 128] },
+            )
+        };
+
+        for packet in [
+            fragment(1, 0, 0),
+            fragment(2, 0, MAX_FRAGMENTS + 1),
+            fragment(3, 2, 2),
+            fragment(4, 0, 2),
+            fragment(4, 1, 3),
+            fragment(4, 0, 2),
+        ] {
+            network.send(21, packet);
+        }
+        assert_eq!(endpoints.poll(&network), vec![]);
+        let stats = endpoints.stats();
+        assert_eq!((stats.fragments_rejected, stats.fragments_delivered), (4, 1));
+
+        network.send(21, fragment(4, 1, 2));
+        let events = collect(&network, &mut endpoints, 4);
+        assert!(events.contains(&EndpointEvent::Delivered(Message { from: 1, to: 21, session_id: 4, data: vec![7, 7] })));
+        assert_eq!(endpoints.stats().acks_received, 3);
+        network.shutdown();
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod test {
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::network::{NodeId, SourceRoutingHeader};
+    use wg_2024::packet::{FloodRequest, NodeType, Packet, PacketType};
+
+    use crate::drone::core::{Action, DroneCore};
+    use crate::simulation::Simulation;
+
+    const DRONES: [NodeId; 6] = [11, 12, 13, 14, 15, 16];
+
+    fn flood_request(path_trace: Vec<(NodeId, NodeType)>) -> Packet {
+        Packet::new_flood_request(
+            SourceRoutingHeader::new(vec![], 0),
+            1,
+            FloodRequest {
+                flood_id: 1,
+                initiator_id: 1,
+                path_trace,
+            },
+        )
+    }
+
+    /// Client 1 connected to drone 11 of a full mesh of drones, with a flood
+    /// request from the client in the channel of drone 11
+    fn mesh(max_flood_path: Option<usize>) -> Simulation {
+        let mut simulation = Simulation::new(1);
+        simulation.add_endpoint(1);
+        for id in DRONES {
+            simulation.add_drone(id, 0.0);
+            if let Some(max) = max_flood_path {
+                simulation.configure_drone(id, |drone| drone.with_max_flood_path(max));
+            }
+        }
+        for (i, a) in DRONES.iter().enumerate() {
+            for b in DRONES[i + 1..].iter() {
+                simulation.connect(*a, *b);
+            }
+        }
+        simulation.connect(1, 11);
+
+        simulation.inject(0, 11, flood_request(vec![(1, NodeType::Client)]));
+        assert!(simulation.run_until_idle(1000));
+        simulation
+    }
+
+    /// Returns the path traces of the flood requests sent by the drones
+    fn forwarded_traces(simulation: &Simulation) -> Vec<usize> {
+        simulation.events()
+            .iter()
+            .filter_map(|event| match &event.event {
+                DroneEvent::PacketSent(Packet { pack_type: PacketType::FloodRequest(request), .. }) => {
+                    Some(request.path_trace.len())
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    /// A drone whose entry makes the path trace reach the maximum answers the request
+    fn max_flood_path_answers() {
+        let mut core = DroneCore::new(12, [11, 13], 0.0).with_max_flood_path(3);
+        let actions = core.handle_packet(flood_request(vec![(1, NodeType::Client), (11, NodeType::Drone)]));
+
+        assert_eq!(actions.len(), 1);
+        let Action::Forward(11, Packet { pack_type: PacketType::FloodResponse(response), .. }) = &actions[0] else {
+            panic!("Expected a flood response, got {:?}", actions);
+        };
+        assert_eq!(response.path_trace.len(), 3);
+    }
+
+    #[test]
+    /// In a full mesh the flood requests never carry more than the maximum trace,
+    /// and the flood costs less than without the limit
+    fn flood_bounded() {
+        let unbounded = mesh(None);
+        let bounded = mesh(Some(3));
+
+        let traces = forwarded_traces(&bounded);
+        assert!(!traces.is_empty());
+        assert!(traces.iter().all(|len| *len < 3), "{:?}", traces);
+        assert!(forwarded_traces(&unbounded).iter().any(|len| *len >= 3));
+        assert!(bounded.events().len() < unbounded.events().len());
+
+        // every response reaches the client, with a trace no longer than the maximum
+        let responses = bounded.deliveries()
+            .iter()
+            .filter_map(|delivery| match &delivery.packet.pack_type {
+                PacketType::FloodResponse(response) => Some(response.path_trace.len()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!responses.is_empty());
+        assert!(responses.iter().all(|len| *len <= 3), "{:?}", responses);
+    }
+}
@@ -9,6 +9,7 @@ mod test {
     use crossbeam_channel::unbounded;
     use wg_2024::network::SourceRoutingHeader;
 
+    use crate::conformance;
     use crate::drone::RustDoIt;
     use std::collections::HashMap;
     use std::thread;
@@ -18,21 +19,6 @@ mod test {
     fn init() {
         let _ = env_logger::builder().is_test(true).try_init();
     }
-    fn create_sample_packet() -> Packet {
-        Packet {
-            pack_type: PacketType::MsgFragment(Fragment {
-                fragment_index: 1,
-                total_n_fragments: 1,
-                length: 128,
-                data: [1; 128],
-            }),
-            routing_header: SourceRoutingHeader {
-                hop_index: 1,
-                hops: vec![1, 11, 12, 21],
-            },
-            session_id: 1,
-        }
-    }
 
     fn create_custom_routing_header(hop_index: usize, hops: Vec<NodeId>) -> SourceRoutingHeader {
         SourceRoutingHeader { hop_index, hops }
@@ -55,38 +41,7 @@ mod test {
     /// Test forward functionality of a generic packet for a drone
     pub fn generic_packet_forward() {
         init();
-        let (d_send, d_recv) = unbounded();
-        let (d2_send, d2_recv) = unbounded::<Packet>();
-        let (d_command_send, d_command_recv) = unbounded();
-        let (d_event_send, d_event_recv) = unbounded();
-        let neighbours = HashMap::from([(12, d2_send.clone())]);
-        let mut drone11 = RustDoIt::new(
-            11,
-            d_event_send,
-            d_command_recv,
-            d_recv.clone(),
-            neighbours,
-            0.0,
-        );
-        thread::spawn(move || {
-            drone11.run();
-        });
-
-        let mut msg = create_sample_packet();
-
-        // "Client" sends packet to d
-        d_send.send(msg.clone()).unwrap();
-        msg.routing_header.hop_index += 1;
-        let packet_sent_event = DroneEvent::PacketSent(msg.clone());
-
-        // d2 receives packet from d1
-        let packet_received = d2_recv.recv().unwrap();
-        let event_log = d_event_recv.recv().unwrap();
-
-        assert_eq!(packet_received, msg);
-        assert_eq!(event_log, packet_sent_event);
-
-        d_command_send.send(DroneCommand::Crash).unwrap()
+        conformance::fragment_forward::<RustDoIt>().unwrap();
     }
 
     #[test]
@@ -142,60 +97,7 @@ mod test {
     /// Checks if the packet is dropped by a drone and a Nack is sent back. The drone MUST have 100% packet drop rate, otherwise the test will fail sometimes.
     pub fn generic_fragment_drop() {
         init();
-
-        // Client 1
-        let (c_send, c_recv) = unbounded();
-        // Drone 11
-        let (d_send, d_recv) = unbounded();
-        // SC commands
-        let (d_command_send, d_command_recv) = unbounded();
-        let (d_event_send, d_event_recv) = unbounded();
-
-        let neighbours = HashMap::from([(12, d_send.clone()), (1, c_send.clone())]);
-        let mut drone = RustDoIt::new(
-            11,
-            d_event_send,
-            d_command_recv,
-            d_recv.clone(),
-            neighbours,
-            1.0,
-        );
-
-        // Spawn the drone's run method in a separate thread
-        thread::spawn(move || {
-            drone.run();
-        });
-
-        let msg = create_sample_packet();
-
-        // "Client" sends packet to the drone
-        d_send.send(msg.clone()).unwrap();
-
-        let dropped = Nack {
-            fragment_index: msg.get_fragment_index(),
-            nack_type: NackType::Dropped,
-        };
-        let srh = SourceRoutingHeader {
-            hop_index: 1,
-            hops: vec![11, 1],
-        };
-        let nack_packet = Packet {
-            pack_type: PacketType::Nack(dropped),
-            routing_header: srh,
-            session_id: 1,
-        };
-
-        // msg.routing_header.hop_index += 1;
-
-        let packet_sent_event = DroneEvent::PacketSent(nack_packet.clone());
-        let packet_drop_event = DroneEvent::PacketDropped(msg.clone());
-        let packet_dropped = d_event_recv.recv().unwrap();
-        let nack_sent = d_event_recv.recv().unwrap();
-
-        assert_eq!(packet_sent_event, nack_sent);
-        assert_eq!(packet_drop_event, packet_dropped);
-        assert_eq!(c_recv.recv().unwrap(), nack_packet);
-        d_command_send.send(DroneCommand::Crash).unwrap()
+        conformance::fragment_drop::<RustDoIt>().unwrap();
     }
 
     #[test]
@@ -293,148 +195,14 @@ mod test {
     /// Checks if the packet is dropped by the second drone and a Nack is sent back. The first drone must have 0% PDR and the second one 100% PDR, otherwise the test will fail sometimes.
     pub fn generic_chain_fragment_drop() {
         init();
-
-        // Client 1 channels
-        let (c_send, c_recv) = unbounded();
-        // Server 21 channels
-        let (s_send, _s_recv) = unbounded();
-        // Drone 11
-        let (d_send, d_recv) = unbounded();
-        // Drone 12
-        let (d12_send, d12_recv) = unbounded();
-        // SC - needed to not make the drone crash
-        let (d_command_send, d_command_recv) = unbounded();
-        let (d_event_send, d_event_recv) = unbounded();
-
-        // Drone 11
-        let neighbours11 = HashMap::from([(12, d12_send.clone()), (1, c_send.clone())]);
-        let mut drone1 = RustDoIt::new(
-            11,
-            d_event_send.clone(),
-            d_command_recv.clone(),
-            d_recv.clone(),
-            neighbours11,
-            0.0,
-        );
-        // Drone 12
-        let neighbours12 = HashMap::from([(11, d_send.clone()), (21, s_send.clone())]);
-        let mut drone2 = RustDoIt::new(
-            12,
-            d_event_send.clone(),
-            d_command_recv.clone(),
-            d12_recv.clone(),
-            neighbours12,
-            1.0,
-        );
-
-        // Spawn the drone's run method in a separate thread
-        thread::spawn(move || {
-            drone1.run();
-        });
-        thread::spawn(move || {
-            drone2.run();
-        });
-
-        let mut msg = create_sample_packet();
-
-        // "Client" sends packet to the drone1
-        d_send.send(msg.clone()).unwrap();
-
-        // Client receive an NACK originated from drone2
-        let mut packet_true = Packet {
-            pack_type: PacketType::Nack(Nack {
-                fragment_index: msg.get_fragment_index(),
-                nack_type: NackType::Dropped,
-            }),
-            routing_header: SourceRoutingHeader {
-                hop_index: 2,
-                hops: vec![12, 11, 1],
-            },
-            session_id: 1,
-        };
-
-        let packet_got = c_recv.recv().unwrap();
-        assert_eq!(packet_true, packet_got);
-
-        msg.routing_header.increase_hop_index();
-        let packet_sent_event = DroneEvent::PacketSent(msg.clone());
-
-        // msg.routing_header.increase_hop_index();
-        let packet_drop_event = DroneEvent::PacketDropped(msg.clone());
-        let packet_sent = d_event_recv.recv().unwrap();
-        let packet_dropped = d_event_recv.recv().unwrap();
-        packet_true.routing_header.decrease_hop_index();
-        let nack_sent = d_event_recv.recv().unwrap();
-
-        assert_eq!(packet_sent_event, packet_sent);
-        assert_eq!(packet_drop_event, packet_dropped);
-        assert_eq!(nack_sent, DroneEvent::PacketSent(packet_true.clone()));
-        packet_true.routing_header.increase_hop_index();
-        let nack_sent = d_event_recv.recv().unwrap();
-        assert_eq!(nack_sent, DroneEvent::PacketSent(packet_true.clone()));
-
-        d_command_send.send(DroneCommand::Crash).unwrap();
-        d_command_send.send(DroneCommand::Crash).unwrap();
+        conformance::chain_fragment_drop::<RustDoIt>().unwrap();
     }
 
     #[test]
     /// Test forward functionality of a generic packet for a chain of drones
     pub fn generic_chain_fragment_forward() {
         init();
-
-        // Client 1 channels
-        let (c_send, _c_recv) = unbounded();
-        // Server 21 channels
-        let (s_send, s_recv) = unbounded();
-        // Drone 11
-        let (d_send, d_recv) = unbounded();
-        // Drone 12
-        let (d12_send, d12_recv) = unbounded();
-        // SC - needed to not make the drone crash
-        let (d_command_send, d_command_recv) = unbounded();
-
-        // Drone 11
-        let neighbours11 = HashMap::from([(12, d12_send.clone()), (1, c_send.clone())]);
-        let mut drone1 = RustDoIt::new(
-            11,
-            unbounded().0,
-            d_command_recv.clone(),
-            d_recv.clone(),
-            neighbours11,
-            0.0,
-        );
-        // Drone 12
-        let neighbours12 = HashMap::from([(11, d_send.clone()), (21, s_send.clone())]);
-        let mut drone2 = RustDoIt::new(
-            12,
-            unbounded().0,
-            d_command_recv.clone(),
-            d12_recv.clone(),
-            neighbours12,
-            0.0,
-        );
-
-        // Spawn the drone's run method in a separate thread
-        thread::spawn(move || {
-            drone1.run();
-        });
-        thread::spawn(move || {
-            drone2.run();
-        });
-
-        let msg = create_sample_packet();
-
-        // Client sends packet to the drone
-        d_send.send(msg.clone()).unwrap();
-
-        // Server receives a packet with the same content of msg but with hop_index+2
-        let mut packet_true = msg.clone();
-        packet_true.routing_header.hop_index += 2;
-
-        let packet_got = s_recv.recv().unwrap();
-        assert_eq!(packet_true, packet_got);
-        d_command_send.send(DroneCommand::Crash).unwrap();
-        d_command_send.send(DroneCommand::Crash).unwrap()
+        conformance::chain_fragment_forward::<RustDoIt>().unwrap();
     }
 
     #[test]
@@ -649,82 +417,7 @@ mod test {
     /// Test the generation of a flood response due to an isolated drone (only neighbour the one who sent the flood request)
     pub fn flood_response_isolation() {
         init();
-
-        // Client 1 channels
-        let (c_send, c_recv) = unbounded();
-        // Drone 11
-        let (d_send, d_recv) = unbounded();
-        // Drone 12
-        let (d12_send, d12_recv) = unbounded();
-        // SC - needed to not make the drone crash
-        let (d_command_send, d_command_recv) = unbounded();
-
-        // Drone 11
-        let neighbours11 = HashMap::from([(12, d12_send.clone()), (1, c_send.clone())]);
-        let mut drone1 = RustDoIt::new(
-            11,
-            unbounded().0,
-            d_command_recv.clone(),
-            d_recv.clone(),
-            neighbours11,
-            0.0,
-        );
-        // Drone 12
-        let neighbours12 = HashMap::from([(11, d_send.clone())]);
-        let mut drone2 = RustDoIt::new(
-            12,
-            unbounded().0,
-            d_command_recv.clone(),
-            d12_recv.clone(),
-            neighbours12,
-            0.0,
-        );
-
-        // Spawn the drone's run method in a separate thread
-        thread::spawn(move || {
-            drone1.run();
-        });
-        thread::spawn(move || {
-            drone2.run();
-        });
-
-        let srh = create_custom_routing_header(
-            // to use in flood_response
-            0,
-            vec![],
-        );
-
-        let flood_request = create_custom_packet(
-            srh,
-            FloodRequest(wg_2024::packet::FloodRequest {
-                flood_id: 0,
-                initiator_id: 1,
-                path_trace: vec![(1, NodeType::Client)],
-            }),
-            0,
-        );
-
-        // Client sends packet to the drone
-        d_send.send(flood_request.clone()).unwrap();
-
-        let srh = create_custom_routing_header(2, vec![12, 11, 1]);
-        let packet_true = create_custom_packet(
-            srh,
-            FloodResponse(wg_2024::packet::FloodResponse {
-                flood_id: 0,
-                path_trace: vec![
-                    (1, NodeType::Client),
-                    (11, NodeType::Drone),
-                    (12, NodeType::Drone),
-                ],
-            }),
-            0,
-        );
-
-        let packet_got = c_recv.recv().unwrap();
-        assert_eq!(packet_got, packet_true);
-        d_command_send.send(DroneCommand::Crash).unwrap();
-        d_command_send.send(DroneCommand::Crash).unwrap();
+        conformance::flood_response_isolation::<RustDoIt>().unwrap();
     }
 
     #[test]
@@ -849,217 +542,19 @@ mod test {
     #[test]
     fn destination_is_drone() {
         init();
-
-        let (c_send, c_recv) = unbounded();
-        // Server 21 channels
-        let (s_send, _s_recv) = unbounded();
-        // Drone 11
-        let (d11_send, d11_recv) = unbounded();
-        // Drone 12
-        let (d12_send, d12_recv) = unbounded();
-        // SC - needed to not make the drone crash
-        let (d_command_send, d_command_recv) = unbounded();
-
-        let (d_event_send, _d_event_recv) = unbounded();
-
-        let neighbours11 = HashMap::from([(12, d12_send.clone()), (1, c_send.clone())]);
-        let mut drone1 = RustDoIt::new(
-            11,
-            d_event_send.clone(),
-            d_command_recv.clone(),
-            d11_recv.clone(),
-            neighbours11,
-            0.0,
-        );
-
-        let neighbours12 = HashMap::from([(11, d11_send.clone()), (21, s_send.clone())]);
-        let mut drone2 = RustDoIt::new(
-            12,
-            d_event_send.clone(),
-            d_command_recv.clone(),
-            d12_recv.clone(),
-            neighbours12,
-            0.0,
-        );
-
-        thread::spawn(move || {
-            drone1.run();
-        });
-        thread::spawn(move || {
-            drone2.run();
-        });
-
-        let srh = SourceRoutingHeader::new(vec![1, 11, 12], 1);
-        let packet = Packet {
-            pack_type: PacketType::MsgFragment(Fragment {
-                fragment_index: 1,
-                total_n_fragments: 1,
-                length: 128,
-                data: [1; 128],
-            }),
-            routing_header: srh,
-            session_id: 1,
-        };
-
-        d11_send.send(packet.clone()).unwrap();
-
-        let expected = Packet {
-            pack_type: PacketType::Nack(Nack {
-                fragment_index: packet.get_fragment_index(),
-                nack_type: NackType::DestinationIsDrone,
-            }),
-            routing_header: SourceRoutingHeader::new(vec![12, 11, 1], 2),
-            session_id: 1,
-        };
-
-        let got = c_recv.recv().unwrap();
-        assert_eq!(got, expected);
-        d_command_send.send(DroneCommand::Crash).unwrap();
-        d_command_send.send(DroneCommand::Crash).unwrap();
+        conformance::destination_is_drone::<RustDoIt>().unwrap();
     }
 
     #[test]
     fn error_in_routing() {
         init();
-
-        let (c_send, c_recv) = unbounded();
-        // Server 21 channels
-        let (s_send, _s_recv) = unbounded();
-        // Drone 11
-        let (d11_send, d11_recv) = unbounded();
-        // Drone 12
-        let (d12_send, d12_recv) = unbounded();
-        // SC - needed to not make the drone crash
-        let (d_command_send, d_command_recv) = unbounded();
-
-        let (d_event_send, _d_event_recv) = unbounded();
-
-        let neighbours11 = HashMap::from([(12, d12_send.clone()), (1, c_send.clone())]);
-        let mut drone1 = RustDoIt::new(
-            11,
-            d_event_send.clone(),
-            d_command_recv.clone(),
-            d11_recv.clone(),
-            neighbours11,
-            0.0,
-        );
-
-        let neighbours12 = HashMap::from([(11, d11_send.clone()), (21, s_send.clone())]);
-        let mut drone2 = RustDoIt::new(
-            12,
-            d_event_send.clone(),
-            d_command_recv.clone(),
-            d12_recv.clone(),
-            neighbours12,
-            0.0,
-        );
-
-        thread::spawn(move || {
-            drone1.run();
-        });
-        thread::spawn(move || {
-            drone2.run();
-        });
-
-        let srh = SourceRoutingHeader::new(vec![1, 11, 13], 1);
-        let packet = Packet {
-            pack_type: PacketType::MsgFragment(Fragment {
-                fragment_index: 1,
-                total_n_fragments: 1,
-                length: 128,
-                data: [1; 128],
-            }),
-            routing_header: srh,
-            session_id: 1,
-        };
-
-        d11_send.send(packet.clone()).unwrap();
-
-        let expected = Packet {
-            pack_type: PacketType::Nack(Nack {
-                fragment_index: packet.get_fragment_index(),
-                nack_type: NackType::ErrorInRouting(13),
-            }),
-            routing_header: SourceRoutingHeader::new(vec![11, 1], 1),
-            session_id: 1,
-        };
-
-        let got = c_recv.recv().unwrap();
-        assert_eq!(got, expected);
-        d_command_send.send(DroneCommand::Crash).unwrap();
-        d_command_send.send(DroneCommand::Crash).unwrap();
+        conformance::error_in_routing::<RustDoIt>().unwrap();
     }
 
     #[test]
     fn unexpected_recipient() {
         init();
-
-        let (c_send, c_recv) = unbounded();
-        // Server 21 channels
-        let (s_send, _s_recv) = unbounded();
-        // Drone 11
-        let (d11_send, d11_recv) = unbounded();
-        // Drone 12
-        let (d12_send, d12_recv) = unbounded();
-        // SC - needed to not make the drone crash
-        let (d_command_send, d_command_recv) = unbounded();
-
-        let (d_event_send, _d_event_recv) = unbounded();
-
-        let neighbours11 = HashMap::from([(12, d12_send.clone()), (1, c_send.clone())]);
-        let mut drone1 = RustDoIt::new(
-            11,
-            d_event_send.clone(),
-            d_command_recv.clone(),
-            d11_recv.clone(),
-            neighbours11,
-            0.0,
-        );
-
-        let neighbours12 = HashMap::from([(11, d11_send.clone()), (21, s_send.clone())]);
-        let mut drone2 = RustDoIt::new(
-            12,
-            d_event_send.clone(),
-            d_command_recv.clone(),
-            d12_recv.clone(),
-            neighbours12,
-            0.0,
-        );
-
-        thread::spawn(move || {
-            drone1.run();
-        });
-        thread::spawn(move || {
-            drone2.run();
-        });
-
-        let srh = SourceRoutingHeader::new(vec![1, 12, 11], 1);
-        let packet = Packet {
-            pack_type: PacketType::MsgFragment(Fragment {
-                fragment_index: 1,
-                total_n_fragments: 1,
-                length: 128,
-                data: [1; 128],
-            }),
-            routing_header: srh,
-            session_id: 1,
-        };
-
-        d11_send.send(packet.clone()).unwrap();
-
-        let expected = Packet {
-            pack_type: PacketType::Nack(Nack {
-                fragment_index: packet.get_fragment_index(),
-                nack_type: NackType::UnexpectedRecipient(11),
-            }),
-            routing_header: SourceRoutingHeader::new(vec![11, 1], 1),
-            session_id: 1,
-        };
-
-        let got = c_recv.recv().unwrap();
-        assert_eq!(got, expected);
-        d_command_send.send(DroneCommand::Crash).unwrap();
-        d_command_send.send(DroneCommand::Crash).unwrap();
+        conformance::unexpected_recipient::<RustDoIt>().unwrap();
     }
 
     #[test]
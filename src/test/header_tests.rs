@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod test {
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Fragment, Nack, NackType, Packet, PacketType};
+
+    use crate::drone::core::{Action, DroneCore};
+    use crate::drone::header::{validate_header, MalformedHeader};
+    use crate::drone::policy::LoopPolicy;
+
+    fn fragment(hops: Vec<u8>, hop_index: usize) -> Packet {
+        Packet::new_fragment(
+            SourceRoutingHeader::new(hops, hop_index),
+            1,
+            Fragment {
+                fragment_index: 1,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            },
+        )
+    }
+
+    fn ack(hops: Vec<u8>, hop_index: usize) -> Packet {
+        Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 1 }),
+            routing_header: SourceRoutingHeader::new(hops, hop_index),
+            session_id: 1,
+        }
+    }
+
+    #[test]
+    /// Checks the classification of every kind of malformed header
+    fn validate_headers() {
+        let cases = [
+            (vec![1, 11, 12, 21], 1, Ok(())),
+            (vec![], 0, Err(MalformedHeader::Empty)),
+            (vec![1, 11, 12, 21], 0, Err(MalformedHeader::IndexZero)),
+            (vec![1, 11, 12, 21], 4, Err(MalformedHeader::IndexPastEnd)),
+            (vec![1, 11, 11, 21], 1, Err(MalformedHeader::DuplicateConsecutiveHops(11))),
+            (vec![1, 11, 12, 11, 21], 1, Err(MalformedHeader::Loop(11))),
+        ];
+
+        for (hops, hop_index, expected) in cases {
+            let srh = SourceRoutingHeader::new(hops, hop_index);
+            assert_eq!(validate_header(&srh), expected, "{:?}", srh);
+        }
+    }
+
+    #[test]
+    /// A fragment whose hop index points to its source is dropped,
+    /// an ack is sent to the controller as it is
+    fn index_zero() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0);
+
+        let msg = fragment(vec![1, 11, 12, 21], 0);
+        assert_eq!(core.handle_packet(msg.clone()), vec![Action::Drop(msg)]);
+
+        let ack = ack(vec![21, 12, 11, 1], 0);
+        assert_eq!(
+            core.handle_packet(ack.clone()),
+            vec![Action::Event(DroneEvent::ControllerShortcut(ack))]
+        );
+    }
+
+    #[test]
+    /// A route asking the next hop to send the packet to itself is refused
+    /// with an ErrorInRouting nack naming the repeated hop
+    fn duplicate_consecutive_hops() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0);
+        let msg = fragment(vec![1, 11, 12, 12, 21], 1);
+
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 1,
+                nack_type: NackType::ErrorInRouting(12),
+            }),
+            routing_header: SourceRoutingHeader::new(vec![11, 1], 1),
+            session_id: 1,
+        };
+
+        assert_eq!(core.handle_packet(msg), vec![Action::Forward(1, nack)]);
+    }
+
+    #[test]
+    /// A misrouted packet is refused as such before its route is validated,
+    /// even if the route is malformed
+    fn misrouted_before_malformed() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0).with_loop_policy(LoopPolicy::Nack);
+
+        for hops in [vec![1, 13, 12, 12, 21], vec![1, 13, 12, 13, 21]] {
+            let actions = core.handle_packet(fragment(hops, 1));
+            assert!(
+                matches!(
+                    &actions[..],
+                    [Action::Event(DroneEvent::ControllerShortcut(Packet { pack_type: PacketType::Nack(nack), .. }))]
+                        | [Action::Forward(_, Packet { pack_type: PacketType::Nack(nack), .. })]
+                        if nack.nack_type == NackType::UnexpectedRecipient(11)
+                ),
+                "{:?}",
+                actions
+            );
+        }
+        assert_eq!(core.stats().routing_loops(), 0);
+    }
+
+    #[test]
+    /// A route going through the same drone twice is still forwarded
+    fn loop_is_forwarded() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0);
+        let msg = fragment(vec![1, 11, 12, 11, 21], 1);
+
+        let mut expected = msg.clone();
+        expected.routing_header.hop_index += 1;
+
+        assert_eq!(core.handle_packet(msg), vec![Action::Forward(12, expected)]);
+        assert_eq!(core.stats().routing_loops(), 1);
+    }
+
+    #[test]
+    /// With `LoopPolicy::Nack` a route with a loop is refused with an ErrorInRouting
+    /// nack naming the repeated drone, and the loop is counted
+    fn loop_refused_with_nack() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0).with_loop_policy(LoopPolicy::Nack);
+        let stats = core.stats();
+        let msg = fragment(vec![1, 11, 12, 11, 21], 1);
+
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 1,
+                nack_type: NackType::ErrorInRouting(11),
+            }),
+            routing_header: SourceRoutingHeader::new(vec![11, 1], 1),
+            session_id: 1,
+        };
+
+        assert_eq!(core.handle_packet(msg), vec![Action::Forward(1, nack)]);
+        assert_eq!(stats.routing_loops(), 1);
+    }
+
+    #[test]
+    /// With `LoopPolicy::Shortcut` any packet with a loop goes to the controller
+    fn loop_shortcut() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0).with_loop_policy(LoopPolicy::Shortcut);
+        let msg = fragment(vec![1, 11, 12, 11, 21], 1);
+        let ack = ack(vec![21, 11, 12, 11, 1], 1);
+
+        assert_eq!(
+            core.handle_packet(msg.clone()),
+            vec![Action::Event(DroneEvent::ControllerShortcut(msg))]
+        );
+        assert_eq!(
+            core.handle_packet(ack.clone()),
+            vec![Action::Event(DroneEvent::ControllerShortcut(ack))]
+        );
+        assert_eq!(core.stats().routing_loops(), 2);
+    }
+}
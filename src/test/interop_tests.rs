@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use crossbeam_channel::{select_biased, Receiver, Sender};
+    use wg_2024::controller::{DroneCommand, DroneEvent};
+    use wg_2024::drone::Drone;
+    use wg_2024::network::NodeId;
+    use wg_2024::packet::Packet;
+
+    use crate::drone::RustDoIt;
+    use crate::network::interop::{self, Traffic};
+    use crate::network::{DroneRegistry, Network, NetworkConfig, NetworkError};
+
+    const TOPOLOGY: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [1, 12, 13]
+        pdr = 0.0
+
+        [[drone]]
+        id = 12
+        connected_node_ids = [11, 21]
+        pdr = 0.0
+        implementation = "other"
+
+        [[drone]]
+        id = 13
+        connected_node_ids = [11, 22]
+        pdr = 0.0
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [12]
+
+        [[server]]
+        id = 22
+        connected_drone_ids = [13]
+    "#;
+
+    /// A drone that swallows every packet without telling anyone
+    struct BlackHole {
+        controller_recv: Receiver<DroneCommand>,
+        packet_recv: Receiver<Packet>,
+    }
+
+    impl Drone for BlackHole {
+        fn new(
+            _id: NodeId,
+            _controller_send: Sender<DroneEvent>,
+            controller_recv: Receiver<DroneCommand>,
+            packet_recv: Receiver<Packet>,
+            _packet_send: HashMap<NodeId, Sender<Packet>>,
+            _pdr: f32,
+        ) -> Self {
+            Self { controller_recv, packet_recv }
+        }
+
+        fn run(&mut self) {
+            loop {
+                select_biased! {
+                    recv(self.controller_recv) -> _ => return,
+                    recv(self.packet_recv) -> packet => if packet.is_err() { return },
+                }
+            }
+        }
+    }
+
+    /// A `RustDoIt` that drops every fragment, whatever PDR it is given
+    struct AlwaysDrop(RustDoIt);
+
+    impl Drone for AlwaysDrop {
+        fn new(
+            id: NodeId,
+            controller_send: Sender<DroneEvent>,
+            controller_recv: Receiver<DroneCommand>,
+            packet_recv: Receiver<Packet>,
+            packet_send: HashMap<NodeId, Sender<Packet>>,
+            _pdr: f32,
+        ) -> Self {
+            Self(RustDoIt::new(id, controller_send, controller_recv, packet_recv, packet_send, 1.0))
+        }
+
+        fn run(&mut self) {
+            self.0.run();
+        }
+    }
+
+    fn traffic() -> Vec<Traffic> {
+        vec![
+            Traffic { from: 1, to: 21, fragments: 20 },
+            Traffic { from: 1, to: 22, fragments: 20 },
+        ]
+    }
+
+    #[test]
+    /// Checks that the implementation of each drone is read from the config
+    fn config_implementation() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let implementations = config.drone
+            .iter()
+            .map(|drone| drone.implementation())
+            .collect::<Vec<_>>();
+        assert_eq!(implementations, vec!["rust_do_it", "other", "rust_do_it"]);
+    }
+
+    #[test]
+    /// A drone naming an implementation missing from the registry is refused
+    fn unknown_implementation() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let err = Network::start(&config, &DroneRegistry::default()).unwrap_err();
+        assert_eq!(err, NetworkError::UnknownImplementation {
+            drone: 12,
+            implementation: "other".to_string(),
+        });
+    }
+
+    #[test]
+    /// Two implementations behaving correctly are not reported
+    fn interop_all_conforming() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let mut registry = DroneRegistry::default();
+        registry.register::<RustDoIt>("other");
+
+        let network = Network::start(&config, &registry).unwrap();
+        let report = interop::run(&network, &traffic(), Duration::from_secs(2));
+        network.shutdown();
+
+        assert_eq!(report.delivered, 40);
+        assert!(report.suspicious().is_empty(), "{}", report);
+        assert_eq!(report.implementation("other").unwrap().handled, 20);
+        assert_eq!(report.implementation("rust_do_it").unwrap().handled, 60);
+    }
+
+    #[test]
+    /// A drone losing fragments without any event is blamed
+    fn interop_black_hole() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let mut registry = DroneRegistry::default();
+        registry.register::<BlackHole>("other");
+
+        let network = Network::start(&config, &registry).unwrap();
+        let report = interop::run(&network, &traffic(), Duration::from_millis(300));
+        network.shutdown();
+
+        assert_eq!(report.delivered, 20);
+        assert_eq!(report.suspicious(), vec!["other"]);
+        assert_eq!(report.implementation("other").unwrap().lost, 20);
+    }
+
+    #[test]
+    /// A drone dropping more than its PDR allows is reported
+    fn interop_inconsistent_pdr() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let mut registry = DroneRegistry::default();
+        registry.register::<AlwaysDrop>("other");
+
+        let network = Network::start(&config, &registry).unwrap();
+        let report = interop::run(&network, &traffic(), Duration::from_secs(2));
+        network.shutdown();
+
+        let other = report.implementation("other").unwrap();
+        assert_eq!(other.dropped, 20);
+        assert!(!other.drop_rate_consistent());
+        assert_eq!(report.suspicious(), vec!["other"]);
+    }
+}
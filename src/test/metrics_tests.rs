@@ -0,0 +1,113 @@
+#[cfg(all(test, feature = "metrics"))]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::time::{Duration, Instant};
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, Packet, PacketType};
+
+    use crate::metrics::Metrics;
+    use crate::network::{DroneRegistry, Network, NetworkConfig};
+
+    const TOPOLOGY: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [1, 12]
+        pdr = 0.0
+
+        [[drone]]
+        id = 12
+        connected_node_ids = [11, 21]
+        pdr = 1.0
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [12]
+    "#;
+
+    /// Sends a request to the server, giving up if it does not accept the connection within a second
+    fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(1)).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    /// Sends a fragment from the client to the server and waits for the nack
+    /// of the drone dropping it
+    fn send_fragment(network: &Network) {
+        let packet = Packet::new_fragment(
+            SourceRoutingHeader::new(vec![1, 11, 12, 21], 1),
+            1,
+            Fragment {
+                fragment_index: 0,
+                total_n_fragments: 1,
+                length: 128,
+                data: [0; 128],
+            },
+        );
+        assert!(network.send(11, packet));
+        let nack = network.receiver(1).unwrap().recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(matches!(nack.pack_type, PacketType::Nack(_)));
+    }
+
+    #[test]
+    /// The counters of the drones of a network are rendered in the text format
+    fn render_network_metrics() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let network = Network::start(&config, &DroneRegistry::default()).unwrap();
+        let metrics = Metrics::from_network(&network);
+        send_fragment(&network);
+
+        // the nack is counted by 11 after it reached the client, before its event is sent
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut events = network.events();
+        while !events.iter().any(|(drone, event)| match event {
+            DroneEvent::PacketSent(packet) => *drone == 11 && matches!(packet.pack_type, PacketType::Nack(_)),
+            _ => false,
+        }) {
+            assert!(network.wait(deadline), "no nack sent by 11: {:?}", events);
+            events.extend(network.events());
+        }
+
+        let text = metrics.render();
+        network.shutdown();
+
+        assert!(text.contains("# TYPE rust_do_it_packets_forwarded_total counter"));
+        assert!(text.contains("rust_do_it_packets_forwarded_total{drone=\"11\",implementation=\"rust_do_it\"} 2"), "{}", text);
+        assert!(text.contains("rust_do_it_packets_dropped_total{drone=\"12\",implementation=\"rust_do_it\"} 1"), "{}", text);
+        assert!(text.contains(
+            "rust_do_it_nacks_total{drone=\"12\",implementation=\"rust_do_it\",nack_type=\"dropped\"} 1"
+        ), "{}", text);
+    }
+
+    #[test]
+    /// The metrics are served over HTTP on localhost
+    fn serve_metrics() {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        let network = Network::start(&config, &DroneRegistry::default()).unwrap();
+        let server = Metrics::from_network(&network).serve("127.0.0.1:0").unwrap();
+        send_fragment(&network);
+
+        let response = get(server.local_addr(), "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.contains("text/plain; version=0.0.4"));
+        assert!(response.contains("rust_do_it_packets_dropped_total{drone=\"12\",implementation=\"rust_do_it\"} 1"));
+
+        assert!(get(server.local_addr(), "/other").starts_with("HTTP/1.1 404"));
+
+        // a client sending nothing does not keep the others waiting for ever
+        let idle = TcpStream::connect(server.local_addr()).unwrap();
+        assert!(get(server.local_addr(), "/metrics").starts_with("HTTP/1.1 200 OK"));
+        drop(idle);
+
+        server.shutdown();
+        network.shutdown();
+    }
+}
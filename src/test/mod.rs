@@ -1 +1,26 @@
+// the baseline tests keep a disabled test and its unused helpers
+#[allow(unused_imports, unused_variables, dead_code)]
 mod general_tests;
+mod simulation_tests;
+mod scenario_tests;
+mod async_tests;
+mod core_tests;
+mod header_tests;
+mod policy_tests;
+mod repair_tests;
+mod topology_tests;
+mod flood_tests;
+mod rate_limit_tests;
+mod pdr_schedule_tests;
+mod byzantine_tests;
+mod acl_tests;
+mod metrics_tests;
+mod dot_tests;
+mod endpoint_tests;
+mod shell_tests;
+mod chaos_tests;
+mod partition_tests;
+mod checksum_tests;
+mod property_tests;
+mod conformance_tests;
+mod interop_tests;
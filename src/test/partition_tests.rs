@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+    use std::time::{Duration, Instant};
+
+    use crate::network::{DroneRegistry, EndpointEvent, Endpoints, Network, NetworkConfig, PartitionError};
+
+    // Two chains joined by the link 12 - 13, with the client on one side and the servers on both
+    const TOPOLOGY: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [1, 12]
+        pdr = 0.0
+
+        [[drone]]
+        id = 12
+        connected_node_ids = [11, 13, 21]
+        pdr = 0.0
+
+        [[drone]]
+        id = 13
+        connected_node_ids = [12, 14]
+        pdr = 0.0
+
+        [[drone]]
+        id = 14
+        connected_node_ids = [13, 22]
+        pdr = 0.0
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [12]
+
+        [[server]]
+        id = 22
+        connected_drone_ids = [14]
+    "#;
+
+    fn network() -> Network {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        Network::start(&config, &DroneRegistry::default()).unwrap()
+    }
+
+    /// Floods from the endpoint and returns the nodes found by the responses received within half a second
+    fn discover(network: &Network, endpoints: &mut Endpoints, from: u8) -> BTreeSet<u8> {
+        let flood_id = endpoints.flood(network, from).unwrap();
+        let deadline = Instant::now() + Duration::from_millis(500);
+        let mut found = BTreeSet::new();
+        loop {
+            endpoints.handle_events(network, &network.events());
+            for event in endpoints.poll(network) {
+                if let EndpointEvent::FloodResponse { node, flood_id: id, path_trace } = event {
+                    if node == from && id == flood_id {
+                        found.extend(path_trace.iter().map(|(id, _)| *id));
+                    }
+                }
+            }
+            if !network.wait(deadline) {
+                return found;
+            }
+        }
+    }
+
+    #[test]
+    /// Floods from each side only discover their own group, until the network is healed
+    fn floods_stay_in_partition() {
+        let mut network = network();
+        let mut endpoints = Endpoints::new(&network);
+
+        let partition = network.partition(&[("west", &[1, 11, 12, 21]), ("east", &[13, 14, 22])]).unwrap();
+        assert_eq!(partition.cut_links(), vec![(12, 13)]);
+        assert_eq!(partition.group_of(13), Some("east"));
+        assert!(!network.neighbours(12).contains(&13));
+
+        assert_eq!(discover(&network, &mut endpoints, 1), BTreeSet::from([1, 11, 12, 21]));
+        assert_eq!(discover(&network, &mut endpoints, 22), BTreeSet::from([13, 14, 22]));
+        assert_eq!(network.route(1, 22), None);
+
+        network.heal(partition);
+        assert!(network.neighbours(12).contains(&13));
+        assert_eq!(discover(&network, &mut endpoints, 1), BTreeSet::from([1, 11, 12, 13, 14, 21, 22]));
+        network.shutdown();
+    }
+
+    #[test]
+    /// The nodes left out of the groups form a group of their own, endpoints included
+    fn unlisted_nodes() {
+        let mut network = network();
+        let partition = network.partition(&[("client", &[1])]).unwrap();
+        assert_eq!(partition.cut_links(), vec![(1, 11)]);
+        assert_eq!(partition.group_of(11), None);
+        assert_eq!(network.route(1, 21), None);
+        assert_eq!(network.route(21, 22), Some(vec![21, 12, 13, 14, 22]));
+
+        network.heal(partition);
+        assert_eq!(network.route(1, 21), Some(vec![1, 11, 12, 21]));
+        network.shutdown();
+    }
+
+    #[test]
+    /// Groups naming unknown nodes, or the same node twice, are refused
+    fn invalid_groups() {
+        let mut network = network();
+        assert_eq!(network.partition(&[("a", &[1, 40])]).unwrap_err(), PartitionError::UnknownNode(40));
+        assert_eq!(
+            network.partition(&[("a", &[11]), ("b", &[12, 11])]).unwrap_err(),
+            PartitionError::NodeInTwoGroups { node: 11, first: "a".to_string(), second: "b".to_string() }
+        );
+        assert_eq!(network.neighbours(11), BTreeSet::from([1, 12]));
+        network.shutdown();
+    }
+
+    #[test]
+    /// A link cut by two partitions is restored when both are healed, whatever their order
+    fn overlapping_partitions() {
+        let mut network = network();
+        network.link(11, 13);
+        let halves = network.partition(&[("west", &[1, 11, 12, 21]), ("east", &[13, 14, 22])]).unwrap();
+        assert_eq!(halves.cut_links(), vec![(11, 13), (12, 13)]);
+        let east = network.partition(&[("east", &[13, 14, 22])]).unwrap();
+        assert_eq!(east.cut_links(), vec![(11, 13), (12, 13)]);
+
+        network.heal(halves);
+        assert_eq!(network.route(1, 22), None);
+        assert_eq!(network.neighbours(13), BTreeSet::from([14]));
+
+        network.heal(east);
+        assert_eq!(network.neighbours(13), BTreeSet::from([11, 12, 14]));
+        assert_eq!(network.route(1, 22), Some(vec![1, 11, 13, 14, 22]));
+        network.shutdown();
+    }
+}
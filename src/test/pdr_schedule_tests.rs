@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod test {
+    use wg_2024::controller::DroneCommand;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, Packet, PacketType};
+
+    use crate::drone::command::RustDoItCommand;
+    use crate::drone::core::{Action, DroneCore};
+    use crate::drone::pdr_schedule::{PdrSchedule, PdrScheduleError};
+    use crate::simulation::scenario::Scenario;
+
+    fn create_sample_packet() -> Packet {
+        Packet::new_fragment(
+            SourceRoutingHeader::new(vec![1, 11, 12, 21], 1),
+            1,
+            Fragment {
+                fragment_index: 0,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            },
+        )
+    }
+
+    fn is_forwarded(actions: &[Action]) -> bool {
+        matches!(actions, [Action::Forward(12, _)])
+    }
+
+    #[test]
+    /// Every kind of schedule is evaluated at the given time and clamped between 0 and 1
+    fn evaluate_schedules() {
+        let step = PdrSchedule::Step { before: 0.1, after: 0.6, at: 10 };
+        assert_eq!((step.pdr_at(9), step.pdr_at(10)), (0.1, 0.6));
+
+        let linear = PdrSchedule::Linear { from: 0.0, to: 0.8, start: 10, end: 20 };
+        assert_eq!((linear.pdr_at(0), linear.pdr_at(15), linear.pdr_at(30)), (0.0, 0.4, 0.8));
+
+        let sine = PdrSchedule::Sine { mean: 0.5, amplitude: 0.8, period: 40, phase: 0 };
+        assert_eq!((sine.pdr_at(0), sine.pdr_at(10), sine.pdr_at(30)), (0.5, 1.0, 0.0));
+        let sine = PdrSchedule::Sine { mean: 0.5, amplitude: 0.4, period: 40, phase: 5 };
+        assert!((sine.pdr_at(0) - (0.5 + 0.4 * std::f32::consts::FRAC_1_SQRT_2)).abs() < 1e-6);
+
+        let piecewise = PdrSchedule::Piecewise { points: vec![(20, 0.3), (5, 0.1), (50, 0.0)] };
+        assert_eq!(
+            (piecewise.pdr_at(0), piecewise.pdr_at(5), piecewise.pdr_at(49), piecewise.pdr_at(50)),
+            (0.1, 0.1, 0.3, 0.0)
+        );
+    }
+
+    #[test]
+    /// Piecewise schedules are read from `<time> <pdr>` lines, invalid lines are reported
+    fn parse_piecewise() {
+        let text = "# storm\n0 0.05\n\n100, 0.5\n40\t0.2\n";
+        assert_eq!(
+            PdrSchedule::piecewise_from_str(text),
+            Ok(PdrSchedule::Piecewise { points: vec![(0, 0.05), (40, 0.2), (100, 0.5)] })
+        );
+
+        assert_eq!(PdrSchedule::piecewise_from_str("0 0.1\n10\n"), Err(PdrScheduleError::InvalidLine(2)));
+        assert_eq!(PdrSchedule::piecewise_from_str("ten 0.1"), Err(PdrScheduleError::InvalidLine(1)));
+        assert_eq!(PdrSchedule::piecewise_from_str("0 1.5"), Err(PdrScheduleError::PdrOutOfRange(1)));
+        assert_eq!(PdrSchedule::piecewise_from_str("# nothing\n"), Err(PdrScheduleError::Empty));
+        assert!(matches!(PdrSchedule::piecewise_from_file("/does/not/exist"), Err(PdrScheduleError::Io(_))));
+    }
+
+    #[test]
+    /// The core drops fragments according to the schedule at its clock,
+    /// until a fixed pdr is set again
+    fn core_follows_schedule() {
+        let mut core = DroneCore::new(11, [1, 12], 0.0)
+            .with_pdr_schedule(PdrSchedule::Step { before: 0.0, after: 1.0, at: 10 });
+
+        assert!(is_forwarded(&core.handle_packet(create_sample_packet())));
+        core.set_clock(10);
+        assert_eq!(core.pdr(), 1.0);
+        let actions = core.handle_packet(create_sample_packet());
+        assert!(matches!(&actions[1], Action::Forward(1, nack) if matches!(nack.pack_type, PacketType::Nack(_))));
+
+        core.handle_command(&DroneCommand::SetPacketDropRate(0.0));
+        assert_eq!(core.pdr_schedule(), None);
+        assert!(is_forwarded(&core.handle_packet(create_sample_packet())));
+
+        core.handle_extended_command(&RustDoItCommand::SetPdrSchedule(PdrSchedule::Piecewise { points: vec![(0, 1.0)] }));
+        assert_eq!(core.pdr(), 1.0);
+    }
+
+    #[test]
+    /// A scenario can give a drone a schedule of the virtual time
+    fn scenario_schedule() {
+        let scenario = Scenario::from_toml(r#"
+            [[drone]]
+            id = 11
+            connected_node_ids = [1, 21]
+            pdr = 0.0
+
+            [[client]]
+            id = 1
+            connected_drone_ids = [11]
+
+            [[server]]
+            id = 21
+            connected_drone_ids = [11]
+
+            [[action]]
+            at = 0
+            set_pdr_schedule = { drone = 11, schedule = { kind = "step", before = 0.0, after = 1.0, at = 5 } }
+
+            [[action]]
+            at = 0
+            send = { from = 1, to = 21, messages = 3 }
+
+            [[action]]
+            at = 10
+            send = { from = 1, to = 21, messages = 2 }
+        "#).unwrap();
+
+        let report = scenario.run();
+        assert_eq!((report.fragments_delivered, report.fragments_dropped), (3, 2));
+    }
+}
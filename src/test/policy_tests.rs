@@ -0,0 +1,154 @@
+#[cfg(test)]
+mod test {
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Ack, Fragment, Nack, NackType, Packet, PacketType};
+
+    use crate::drone::core::{Action, DroneCore};
+    use crate::drone::policy::{UnreachableAction, UnreachablePolicy};
+
+    fn fragment(hops: Vec<u8>) -> Packet {
+        Packet::new_fragment(
+            SourceRoutingHeader::new(hops, 1),
+            1,
+            Fragment {
+                fragment_index: 1,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            },
+        )
+    }
+
+    fn ack(hops: Vec<u8>) -> Packet {
+        Packet {
+            pack_type: PacketType::Ack(Ack { fragment_index: 1 }),
+            routing_header: SourceRoutingHeader::new(hops, 1),
+            session_id: 1,
+        }
+    }
+
+    fn policy(action: UnreachableAction) -> UnreachablePolicy {
+        UnreachablePolicy {
+            fragment: action,
+            ack: action,
+            nack: action,
+            flood_response: action,
+        }
+    }
+
+    #[test]
+    /// With `UnreachableAction::Drop` the packets for a missing hop are discarded
+    fn unreachable_drop() {
+        let mut core = DroneCore::new(11, [1], 0.0)
+            .with_unreachable_policy(policy(UnreachableAction::Drop));
+
+        let mut expected = fragment(vec![1, 11, 12, 21]);
+        expected.routing_header.hop_index += 1;
+        assert_eq!(core.handle_packet(fragment(vec![1, 11, 12, 21])), vec![Action::Drop(expected)]);
+    }
+
+    #[test]
+    /// The actions can be swapped: fragments go to the controller, acks get a nack
+    fn unreachable_swapped() {
+        let mut core = DroneCore::new(11, [1], 0.0).with_unreachable_policy(UnreachablePolicy {
+            fragment: UnreachableAction::Shortcut,
+            ack: UnreachableAction::Nack,
+            ..Default::default()
+        });
+
+        let mut expected = fragment(vec![1, 11, 12, 21]);
+        expected.routing_header.hop_index += 1;
+        assert_eq!(
+            core.handle_packet(fragment(vec![1, 11, 12, 21])),
+            vec![Action::Event(DroneEvent::ControllerShortcut(expected))]
+        );
+
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 0,
+                nack_type: NackType::ErrorInRouting(12),
+            }),
+            routing_header: SourceRoutingHeader::new(vec![11, 1], 1),
+            session_id: 1,
+        };
+        assert_eq!(core.handle_packet(ack(vec![1, 11, 12, 21])), vec![Action::Forward(1, nack)]);
+    }
+
+    #[test]
+    /// With `UnreachableAction::Alternate` a fragment is rerouted through the
+    /// neighbour that relayed a packet coming from its destination
+    fn unreachable_alternate() {
+        let mut core = DroneCore::new(11, [1, 13], 0.0)
+            .with_unreachable_policy(policy(UnreachableAction::Alternate));
+
+        // an ack from 21 to 1 through 14 and 13 teaches the route [13, 14, 21]
+        let mut ack = ack(vec![21, 14, 13, 11, 1]);
+        ack.routing_header.hop_index = 3;
+        assert!(matches!(&core.handle_packet(ack)[..], [Action::Forward(1, _)]));
+        assert_eq!(core.known_route(21), Some(&[13, 14, 21][..]));
+
+        let mut expected = fragment(vec![1, 11, 13, 14, 21]);
+        expected.routing_header.hop_index = 2;
+        assert_eq!(core.handle_packet(fragment(vec![1, 11, 12, 21])), vec![Action::Forward(13, expected)]);
+    }
+
+    #[test]
+    /// A rerouted fragment is still subject to the packet drop rate
+    fn unreachable_alternate_dropped() {
+        let mut core = DroneCore::new(11, [1, 13], 1.0)
+            .with_unreachable_policy(policy(UnreachableAction::Alternate));
+
+        let mut ack = ack(vec![21, 14, 13, 11, 1]);
+        ack.routing_header.hop_index = 3;
+        core.handle_packet(ack);
+
+        let actions = core.handle_packet(fragment(vec![1, 11, 12, 21]));
+        assert!(matches!(&actions[0], Action::Event(DroneEvent::PacketDropped(_))), "{:?}", actions);
+        assert!(
+            matches!(&actions[1], Action::Forward(1, nack) if matches!(nack.pack_type, PacketType::Nack(_))),
+            "{:?}",
+            actions
+        );
+    }
+
+    #[test]
+    /// A learned route going back through a hop the fragment already travelled is not taken
+    fn unreachable_alternate_loop() {
+        let mut core = DroneCore::new(11, [1, 13], 0.0)
+            .with_unreachable_policy(policy(UnreachableAction::Alternate));
+
+        // an ack from 21 through 1 and 13 teaches the route [13, 1, 21]
+        let mut ack = ack(vec![21, 1, 13, 11, 2]);
+        ack.routing_header.hop_index = 3;
+        core.handle_packet(ack);
+        assert_eq!(core.known_route(21), Some(&[13, 1, 21][..]));
+
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 1,
+                nack_type: NackType::ErrorInRouting(12),
+            }),
+            routing_header: SourceRoutingHeader::new(vec![11, 1], 1),
+            session_id: 1,
+        };
+        assert_eq!(core.handle_packet(fragment(vec![1, 11, 12, 21])), vec![Action::Forward(1, nack)]);
+    }
+
+    #[test]
+    /// Without a known route `UnreachableAction::Alternate` falls back to the default behaviour
+    fn unreachable_alternate_fallback() {
+        let mut core = DroneCore::new(11, [1], 0.0)
+            .with_unreachable_policy(policy(UnreachableAction::Alternate));
+
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 1,
+                nack_type: NackType::ErrorInRouting(12),
+            }),
+            routing_header: SourceRoutingHeader::new(vec![11, 1], 1),
+            session_id: 1,
+        };
+        assert_eq!(core.handle_packet(fragment(vec![1, 11, 12, 21])), vec![Action::Forward(1, nack)]);
+    }
+}
@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+    use proptest::sample::subsequence;
+    use wg_2024::controller::DroneEvent;
+    use wg_2024::network::{NodeId, SourceRoutingHeader};
+    use wg_2024::packet::{Ack, FloodRequest, Fragment, Nack, NackType, NodeType, Packet, PacketType};
+
+    use crate::drone::core::{Action, DroneCore};
+
+    /// The ids used for the generated topologies, small enough to make repeated hops likely
+    const IDS: std::ops::RangeInclusive<NodeId> = 1..=12;
+
+    /// A drone with a random set of neighbours and a packet that went through
+    /// a random route, which may or may not have this drone as current hop
+    #[derive(Debug, Clone)]
+    struct Scenario {
+        id: NodeId,
+        neighbours: Vec<NodeId>,
+        pdr: f32,
+        seed: u64,
+        packet: Packet,
+    }
+
+    fn pack_type() -> impl Strategy<Value = PacketType> {
+        prop_oneof![
+            3 => any::<u64>().prop_map(|fragment_index| PacketType::MsgFragment(Fragment {
+                fragment_index,
+                total_n_fragments: fragment_index.saturating_add(1),
+                length: 128,
+                data: [0; 128],
+            })),
+            1 => any::<u64>().prop_map(|fragment_index| PacketType::Ack(Ack { fragment_index })),
+            1 => any::<u64>().prop_map(|fragment_index| PacketType::Nack(Nack {
+                fragment_index,
+                nack_type: NackType::Dropped,
+            })),
+        ]
+    }
+
+    fn scenario() -> impl Strategy<Value = Scenario> {
+        (
+            prop::collection::vec(IDS, 2..8),
+            any::<prop::sample::Index>(),
+            any::<bool>(),
+            subsequence(IDS.collect::<Vec<_>>(), 0..8),
+            prop_oneof![Just(0.0f32), Just(1.0f32), 0.0f32..1.0],
+            any::<u64>(),
+            pack_type(),
+            any::<u64>(),
+        ).prop_flat_map(|(hops, index, on_route, neighbours, pdr, seed, pack_type, session_id)| {
+            let hop_index = 1 + index.index(hops.len() - 1);
+            // most of the packets reach the drone they are meant for
+            let id_strategy = if on_route {
+                Just(hops[hop_index]).boxed()
+            } else {
+                IDS.boxed()
+            };
+            id_strategy.prop_map(move |id| Scenario {
+                id,
+                neighbours: neighbours.iter().copied().filter(|n| *n != id).collect(),
+                pdr,
+                seed,
+                packet: Packet {
+                    pack_type: pack_type.clone(),
+                    routing_header: SourceRoutingHeader { hop_index, hops: hops.clone() },
+                    session_id,
+                },
+            })
+        })
+    }
+
+    fn flood_scenario() -> impl Strategy<Value = (NodeId, Vec<NodeId>, Packet)> {
+        (
+            IDS,
+            subsequence(IDS.collect::<Vec<_>>(), 1..8),
+            prop::collection::vec(IDS, 0..6),
+            any::<u64>(),
+        ).prop_map(|(id, neighbours, trace, flood_id)| {
+            let initiator_id = trace.first().copied().unwrap_or(1);
+            let path_trace = trace
+                .iter()
+                .enumerate()
+                .map(|(i, n)| (*n, if i == 0 { NodeType::Client } else { NodeType::Drone }))
+                .collect();
+            let packet = Packet::new_flood_request(
+                SourceRoutingHeader::new(vec![], 0),
+                0,
+                FloodRequest { flood_id, initiator_id, path_trace },
+            );
+            (id, neighbours.into_iter().filter(|n| *n != id).collect(), packet)
+        })
+    }
+
+    fn run(scenario: &Scenario) -> Vec<Action> {
+        let mut core = DroneCore::new(scenario.id, scenario.neighbours.iter().copied(), scenario.pdr)
+            .with_seed(scenario.seed);
+        core.handle_packet(scenario.packet.clone())
+    }
+
+    /// Returns the route a nack generated by the drone must follow
+    fn expected_nack_route(scenario: &Scenario, nack_type: NackType) -> Vec<NodeId> {
+        let srh = &scenario.packet.routing_header;
+        let mut route = srh.hops[..=srh.hop_index].to_vec();
+        if let NackType::UnexpectedRecipient(_) = nack_type {
+            route.pop();
+            route.push(scenario.id);
+        }
+        route.reverse();
+        route
+    }
+
+    proptest! {
+        #[test]
+        /// Every packet produces exactly one forward (of the packet or of a nack) or one shortcut
+        fn exactly_one_outcome(scenario in scenario()) {
+            let actions = run(&scenario);
+            let outcomes = actions.iter().filter(|action| matches!(
+                action,
+                Action::Forward(_, _) | Action::Event(DroneEvent::ControllerShortcut(_))
+            )).count();
+            prop_assert_eq!(outcomes, 1, "actions: {:?}", actions);
+        }
+
+        #[test]
+        /// A forwarded packet keeps its route and its hop index increases by one
+        fn hop_index_increases_by_one(scenario in scenario()) {
+            let srh = &scenario.packet.routing_header;
+            for action in run(&scenario) {
+                let packet = match action {
+                    Action::Forward(_, packet) => packet,
+                    Action::Event(DroneEvent::ControllerShortcut(packet)) => packet,
+                    _ => continue,
+                };
+                if packet.pack_type == scenario.packet.pack_type {
+                    prop_assert_eq!(&packet.routing_header.hops, &srh.hops);
+                    prop_assert_eq!(packet.routing_header.hop_index, srh.hop_index + 1);
+                }
+            }
+        }
+
+        #[test]
+        /// The nacks generated for a fragment go back along the traversed part of the route
+        fn nack_route_is_reversed_prefix(scenario in scenario()) {
+            prop_assume!(matches!(scenario.packet.pack_type, PacketType::MsgFragment(_)));
+            for action in run(&scenario) {
+                let (to, packet) = match action {
+                    Action::Forward(to, packet) => (Some(to), packet),
+                    Action::Event(DroneEvent::ControllerShortcut(packet)) => (None, packet),
+                    _ => continue,
+                };
+                let PacketType::Nack(nack) = packet.pack_type else { continue };
+
+                let expected = expected_nack_route(&scenario, nack.nack_type);
+                prop_assert_eq!(&packet.routing_header.hops, &expected);
+                prop_assert_eq!(packet.routing_header.hop_index, 1);
+                if let Some(to) = to {
+                    prop_assert_eq!(Some(to), packet.routing_header.current_hop());
+                }
+            }
+        }
+
+        #[test]
+        /// Flood requests are never sent back to the node they came from
+        fn flood_request_not_sent_back((id, neighbours, packet) in flood_scenario()) {
+            let PacketType::FloodRequest(flood_request) = &packet.pack_type else { unreachable!() };
+            let prev_hop = flood_request.path_trace
+                .last()
+                .map(|(n, _)| *n)
+                .unwrap_or(flood_request.initiator_id);
+
+            let mut core = DroneCore::new(id, neighbours.iter().copied(), 0.0);
+            for action in core.handle_packet(packet.clone()) {
+                if let Action::Forward(to, Packet { pack_type: PacketType::FloodRequest(_), .. }) = action {
+                    prop_assert_ne!(to, prev_hop);
+                    prop_assert!(neighbours.contains(&to));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod test {
+    use wg_2024::network::{NodeId, SourceRoutingHeader};
+    use wg_2024::packet::{FloodRequest, NodeType, Packet, PacketType};
+
+    use crate::drone::core::{Action, DroneCore};
+    use crate::drone::rate_limit::FloodRateLimit;
+    use crate::drone::report::DroneReport;
+
+    const LIMIT: FloodRateLimit = FloodRateLimit { burst: 2, interval: 100 };
+
+    fn flood_request(initiator_id: NodeId, flood_id: u64) -> Packet {
+        Packet::new_flood_request(
+            SourceRoutingHeader::new(vec![], 0),
+            flood_id,
+            FloodRequest {
+                flood_id,
+                initiator_id,
+                path_trace: vec![(initiator_id, NodeType::Client)],
+            },
+        )
+    }
+
+    /// Returns true if the actions forward the flood request
+    fn forwarded(actions: &[Action]) -> bool {
+        actions.iter().any(|action| matches!(
+            action,
+            Action::Forward(_, Packet { pack_type: PacketType::FloodRequest(_), .. })
+        ))
+    }
+
+    #[test]
+    /// The floods over the burst are answered right away and reported
+    fn floods_throttled() {
+        let mut core = DroneCore::new(11, [1, 2, 12, 13], 0.0).with_flood_rate_limit(LIMIT);
+
+        assert!(forwarded(&core.handle_packet(flood_request(1, 1))));
+        assert!(forwarded(&core.handle_packet(flood_request(1, 2))));
+
+        let actions = core.handle_packet(flood_request(1, 3));
+        assert!(!forwarded(&actions));
+        assert_eq!(actions[0], Action::Report(DroneReport::FloodThrottled {
+            drone: 11,
+            initiator: 1,
+            flood_id: 3,
+        }));
+        assert!(matches!(
+            &actions[1],
+            Action::Forward(1, Packet { pack_type: PacketType::FloodResponse(_), .. })
+        ));
+        assert_eq!(core.stats().floods_throttled(), 1);
+
+        // another initiator has its own bucket
+        assert!(forwarded(&core.handle_packet(flood_request(2, 1))));
+    }
+
+    #[test]
+    /// The bucket gets a token back every interval, up to the burst
+    fn floods_refilled() {
+        let mut core = DroneCore::new(11, [1, 12, 13], 0.0).with_flood_rate_limit(LIMIT);
+        let mut flood_id = 0;
+        let mut flood = |core: &mut DroneCore| {
+            flood_id += 1;
+            forwarded(&core.handle_packet(flood_request(1, flood_id)))
+        };
+
+        assert!(flood(&mut core));
+        assert!(flood(&mut core));
+        assert!(!flood(&mut core));
+
+        core.set_clock(150);
+        assert!(flood(&mut core));
+        assert!(!flood(&mut core));
+
+        // the half interval left at 150 is kept
+        core.set_clock(200);
+        assert!(flood(&mut core));
+
+        core.set_clock(10_000);
+        assert!(flood(&mut core));
+        assert!(flood(&mut core));
+        assert!(!flood(&mut core));
+    }
+}
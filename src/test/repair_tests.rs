@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use crossbeam_channel::unbounded;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{FloodRequest, Fragment, NodeType, Packet, PacketType};
+
+    use crate::drone::RustDoIt;
+    use crate::drone::core::{Action, DroneCore};
+    use crate::drone::report::DroneReport;
+
+    fn fragment(hops: Vec<u8>) -> Packet {
+        Packet::new_fragment(
+            SourceRoutingHeader::new(hops, 1),
+            1,
+            Fragment {
+                fragment_index: 1,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            },
+        )
+    }
+
+    /// A flood request that went through 13 and 14, teaching the link between them
+    fn flood_request() -> Packet {
+        Packet::new_flood_request(
+            SourceRoutingHeader::new(vec![], 0),
+            1,
+            FloodRequest {
+                flood_id: 1,
+                initiator_id: 1,
+                path_trace: vec![(1, NodeType::Client), (14, NodeType::Drone), (13, NodeType::Drone)],
+            },
+        )
+    }
+
+    #[test]
+    /// The missing next hop is replaced by a neighbour linked to the hop after it
+    fn repair_with_linked_neighbour() {
+        let mut core = DroneCore::new(11, [1, 13], 0.0).with_local_repair(true);
+        core.handle_packet(flood_request());
+
+        let mut expected = fragment(vec![1, 11, 13, 14, 21]);
+        expected.routing_header.hop_index = 2;
+        assert_eq!(core.handle_packet(fragment(vec![1, 11, 12, 14, 21])), vec![
+            Action::Report(DroneReport::RouteRepaired {
+                drone: 11,
+                session_id: 1,
+                missing: 12,
+                replacement: Some(13),
+            }),
+            Action::Forward(13, expected),
+        ]);
+        assert_eq!(core.stats().route_repairs(), 1);
+    }
+
+    #[test]
+    /// The missing next hop is removed when the hop after it is a neighbour
+    fn repair_by_skipping() {
+        let mut core = DroneCore::new(11, [1, 14], 0.0).with_local_repair(true);
+
+        let mut expected = fragment(vec![1, 11, 14, 21]);
+        expected.routing_header.hop_index = 2;
+        let actions = core.handle_packet(fragment(vec![1, 11, 12, 14, 21]));
+        assert_eq!(actions.last(), Some(&Action::Forward(14, expected)));
+    }
+
+    #[test]
+    /// Without local repair the drone sends back a nack, as before
+    fn no_repair_by_default() {
+        let mut core = DroneCore::new(11, [1, 13], 0.0);
+        core.handle_packet(flood_request());
+
+        let actions = core.handle_packet(fragment(vec![1, 11, 12, 14, 21]));
+        assert!(matches!(
+            &actions[..],
+            [Action::Forward(1, Packet { pack_type: PacketType::Nack(_), .. })]
+        ));
+    }
+
+    #[test]
+    /// `RustDoIt` sends the repair on its report channel and the packet on the new route
+    fn repair_reported() {
+        let (controller_send, event_recv) = unbounded();
+        let (_command_send, controller_recv) = unbounded();
+        let (_packet_send, packet_recv) = unbounded();
+        let (report_send, report_recv) = unbounded();
+        let (c_send, _c_recv) = unbounded();
+        let (d13_send, d13_recv) = unbounded();
+        let (d12_send, _d12_recv) = unbounded();
+
+        let mut drone = RustDoIt::new(
+            11,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            HashMap::from([(1, c_send), (12, d12_send), (13, d13_send)]),
+            0.0,
+        ).with_local_repair(true).with_reports(report_send);
+
+        drone.handle_packet(flood_request());
+        drone.handle_command(wg_2024::controller::DroneCommand::RemoveSender(12));
+        drone.handle_packet(fragment(vec![1, 11, 12, 14, 21]));
+
+        assert_eq!(report_recv.try_recv().unwrap(), DroneReport::RouteRepaired {
+            drone: 11,
+            session_id: 1,
+            missing: 12,
+            replacement: Some(13),
+        });
+        assert_eq!(d13_recv.try_recv().unwrap().routing_header.hops, vec![1, 11, 13, 14, 21]);
+        assert!(event_recv.try_iter().count() > 0);
+    }
+}
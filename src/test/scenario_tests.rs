@@ -0,0 +1,108 @@
+#[cfg(test)]
+mod test {
+    use crate::simulation::scenario::{Scenario, ScenarioAction, TimedAction};
+
+    const CHAIN: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [1, 12]
+        pdr = 0.0
+
+        [[drone]]
+        id = 12
+        connected_node_ids = [11, 21]
+        pdr = 0.0
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [12]
+    "#;
+
+    #[test]
+    /// The example scenario parses, passes, and gives the same report every time
+    fn example_scenario() {
+        let scenario = Scenario::from_toml(include_str!("../config/scenario.toml")).unwrap();
+        assert_eq!(scenario.seed, 7);
+        assert_eq!(scenario.network.drone.len(), 3);
+        assert_eq!(scenario.actions[1], TimedAction { at: 10, action: ScenarioAction::Crash(3) });
+        assert_eq!(scenario.actions[2], TimedAction { at: 20, action: ScenarioAction::SetPdr { drone: 2, pdr: 0.4 } });
+
+        let report = scenario.run();
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.fragments_sent, 160);
+        assert_eq!(report, scenario.run());
+    }
+
+    #[test]
+    /// The messages sent after a crash take the routes left, or are not sent at all
+    fn crash_breaks_route() {
+        let scenario = Scenario::from_toml(&format!(r#"
+            duration = 100
+            {}
+            [[action]]
+            at = 0
+            send = {{ from = 1, to = 21, messages = 2, fragments = 3 }}
+
+            [[action]]
+            at = 10
+            crash = 12
+
+            [[action]]
+            at = 10
+            send = {{ from = 1, to = 21 }}
+
+            [expect]
+            min_delivery_ratio = 0.9
+            max_dropped = 0
+        "#, CHAIN)).unwrap();
+
+        let report = scenario.run();
+        assert_eq!((report.fragments_sent, report.fragments_delivered), (7, 6));
+        assert_eq!(report.messages_unroutable, 1);
+        assert_eq!(report.checks.iter().map(|check| check.passed).collect::<Vec<_>>(), vec![false, true]);
+        assert!(!report.passed());
+        assert!(report.to_string().ends_with("result: FAIL"));
+    }
+
+    #[test]
+    /// A new link opens a route, and the pdr set by the timeline drops the fragments
+    fn link_and_pdr() {
+        let scenario = Scenario::from_toml(&format!(r#"
+            {}
+            [[server]]
+            id = 22
+            connected_drone_ids = []
+
+            [[action]]
+            at = 0
+            link = [12, 22]
+
+            [[action]]
+            at = 0
+            set_pdr = {{ drone = 12, pdr = 1.0 }}
+
+            [[action]]
+            at = 1
+            send = {{ from = 1, to = 22, messages = 5 }}
+
+            [expect]
+            max_delivery_ratio = 0.0
+            max_dropped = 5
+        "#, CHAIN)).unwrap();
+
+        let report = scenario.run();
+        assert_eq!((report.fragments_sent, report.fragments_dropped), (5, 5));
+        assert!(report.passed(), "{}", report);
+    }
+
+    #[test]
+    /// Misspelled expectations are rejected instead of being ignored
+    fn unknown_expectation() {
+        let scenario = Scenario::from_toml(&format!("{}\n[expect]\nmin_delivery = 0.5\n", CHAIN));
+        assert!(scenario.is_err());
+    }
+}
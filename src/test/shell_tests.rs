@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use crate::network::{DroneRegistry, Network, NetworkConfig, Shell, ShellCommand, ShellError};
+
+    const TOPOLOGY: &str = r#"
+        [[drone]]
+        id = 11
+        connected_node_ids = [1, 12]
+        pdr = 0.0
+
+        [[drone]]
+        id = 12
+        connected_node_ids = [11, 21]
+        pdr = 0.0
+
+        [[drone]]
+        id = 13
+        connected_node_ids = []
+        pdr = 0.0
+
+        [[client]]
+        id = 1
+        connected_drone_ids = [11]
+
+        [[server]]
+        id = 21
+        connected_drone_ids = [12]
+    "#;
+
+    fn shell() -> Shell {
+        let config = NetworkConfig::from_toml(TOPOLOGY).unwrap();
+        Shell::new(Network::start(&config, &DroneRegistry::default()).unwrap())
+    }
+
+    /// Polls the shell until a line containing `text` is printed or a second has passed,
+    /// returning every line printed
+    fn wait_for(shell: &mut Shell, text: &str) -> Vec<String> {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        let mut lines = Vec::new();
+        loop {
+            lines.extend(shell.poll());
+            if lines.iter().any(|line: &String| line.contains(text)) || !shell.network().wait(deadline) {
+                return lines;
+            }
+        }
+    }
+
+    #[test]
+    /// Every command is parsed with its arguments, the text of a message can be quoted
+    fn parse_commands() {
+        assert_eq!("crash 3".parse(), Ok(ShellCommand::Crash(3)));
+        assert_eq!("pdr 2 0.5".parse(), Ok(ShellCommand::Pdr(2, 0.5)));
+        assert_eq!("link 1 4".parse(), Ok(ShellCommand::Link(1, 4)));
+        assert_eq!("  unlink   1 2 ".parse(), Ok(ShellCommand::Unlink(1, 2)));
+        assert_eq!(
+            "send 4 6 \"hello  world\"".parse(),
+            Ok(ShellCommand::Send { from: 4, to: 6, text: "hello  world".to_string() })
+        );
+        assert_eq!("flood 4".parse(), Ok(ShellCommand::Flood(4)));
+        assert_eq!("trace on".parse(), Ok(ShellCommand::Trace(true)));
+
+        assert_eq!("".parse::<ShellCommand>(), Err(ShellError::Empty));
+        assert_eq!("jump 3".parse::<ShellCommand>(), Err(ShellError::UnknownCommand("jump".to_string())));
+        assert_eq!("pdr 2".parse::<ShellCommand>(), Err(ShellError::MissingArgument("rate")));
+        assert_eq!("crash x".parse::<ShellCommand>(), Err(ShellError::InvalidArgument("x".to_string())));
+        assert_eq!("crash 3 4".parse::<ShellCommand>(), Err(ShellError::InvalidArgument("4".to_string())));
+        assert_eq!("send 4 6 \"hello".parse::<ShellCommand>(), Err(ShellError::UnclosedQuote));
+    }
+
+    #[test]
+    /// A message is delivered and printed, with the drone events when the trace is on
+    fn send_with_trace() {
+        let mut shell = shell();
+
+        shell.execute_line("trace on").unwrap();
+        shell.execute_line("send 1 21 \"hello there\"").unwrap();
+        let lines = wait_for(&mut shell, "21 received session 1 from 1: hello there");
+        assert!(lines.iter().any(|line| line.starts_with("drone 11 sent fragment 0")));
+        assert!(lines.iter().any(|line| line.starts_with("21 received session 1 from 1: hello there")));
+        shell.shutdown();
+    }
+
+    #[test]
+    /// The commands change the network: a crashed drone leaves the route,
+    /// a new link opens another one
+    fn change_topology() {
+        let mut shell = shell();
+
+        shell.execute_line("pdr 11 0.5").unwrap();
+        assert_eq!(shell.network().pdr(11), Some(0.5));
+
+        shell.execute_line("crash 12").unwrap();
+        assert!(!shell.network().neighbours(11).contains(&12));
+        assert_eq!(shell.execute_line("send 1 21 hi"), Err(ShellError::Unroutable { from: 1, to: 21 }));
+
+        shell.execute_line("link 11 13").unwrap();
+        shell.execute_line("link 13 21").unwrap();
+        assert_eq!(shell.network().route(1, 21), Some(vec![1, 11, 13, 21]));
+        shell.execute_line("flood 1").unwrap();
+        let lines = wait_for(&mut shell, "1 got flood response 1: [1, 11, 13, 21]");
+        assert!(lines.iter().any(|line| line == "1 got flood response 1: [1, 11, 13, 21]"));
+
+        shell.execute_line("unlink 13 21").unwrap();
+        assert_eq!(shell.network().route(1, 21), None);
+        shell.shutdown();
+    }
+
+    #[test]
+    /// Commands naming the wrong kind of node are refused
+    fn invalid_targets() {
+        let mut shell = shell();
+
+        assert_eq!(shell.execute_line("crash 1"), Err(ShellError::NotADrone(1)));
+        assert_eq!(shell.execute_line("pdr 40 0.1"), Err(ShellError::UnknownNode(40)));
+        assert_eq!(shell.execute_line("pdr 11 1.5"), Err(ShellError::InvalidArgument("1.5".to_string())));
+        assert_eq!(shell.execute_line("link 1 21"), Err(ShellError::InvalidLink(1, 21)));
+        assert_eq!(shell.execute_line("send 11 21 hi"), Err(ShellError::NotAnEndpoint(11)));
+        shell.execute_line("crash 13").unwrap();
+        assert_eq!(shell.execute_line("crash 13"), Err(ShellError::Crashed(13)));
+        shell.shutdown();
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod test {
+    use wg_2024::config::{Client, Config, Drone as DroneConfig, Server};
+    use wg_2024::controller::{DroneCommand, DroneEvent};
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{Fragment, Nack, NackType, Packet, PacketType};
+
+    use crate::simulation::Simulation;
+
+    fn create_fragment(fragment_index: u64, hops: Vec<u8>) -> Packet {
+        Packet {
+            pack_type: PacketType::MsgFragment(Fragment {
+                fragment_index,
+                total_n_fragments: 1,
+                length: 128,
+                data: [1; 128],
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops,
+            },
+            session_id: 1,
+        }
+    }
+
+    /// Client 1 - Drone 11 - Drone 12 - Server 21
+    fn create_chain(pdr: f32, seed: u64) -> Simulation {
+        let config = Config {
+            drone: vec![
+                DroneConfig { id: 11, connected_node_ids: vec![1, 12], pdr },
+                DroneConfig { id: 12, connected_node_ids: vec![11, 21], pdr },
+            ],
+            client: vec![Client { id: 1, connected_drone_ids: vec![11] }],
+            server: vec![Server { id: 21, connected_drone_ids: vec![12] }],
+        };
+        Simulation::from_config(&config, seed)
+    }
+
+    #[test]
+    /// Checks that a fragment crosses the chain taking one unit of virtual time per hop
+    fn chain_forward() {
+        let mut simulation = create_chain(0.0, 0);
+        let msg = create_fragment(1, vec![1, 11, 12, 21]);
+
+        simulation.inject(0, 11, msg.clone());
+        assert!(simulation.run_until_idle(10));
+
+        let mut expected = msg.clone();
+        expected.routing_header.hop_index = 3;
+
+        let deliveries = simulation.deliveries();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].node, 21);
+        assert_eq!(deliveries[0].time, 2);
+        assert_eq!(deliveries[0].packet, expected);
+
+        let events = simulation.events();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].time, events[0].drone), (0, 11));
+        assert_eq!((events[1].time, events[1].drone), (1, 12));
+        assert_eq!(events[1].event, DroneEvent::PacketSent(expected));
+    }
+
+    #[test]
+    /// Checks that two simulations with the same seed generate the same events
+    fn deterministic_with_seed() {
+        let run = |seed| {
+            let mut simulation = create_chain(0.5, seed);
+            for i in 0..50 {
+                simulation.inject(i / 5, 11, create_fragment(i, vec![1, 11, 12, 21]));
+            }
+            assert!(simulation.run_until_idle(100));
+            (simulation.events().to_vec(), simulation.deliveries().to_vec())
+        };
+
+        let first = run(42);
+        let second = run(42);
+        assert_eq!(first, second);
+        assert!(first.0.iter().any(|e| matches!(e.event, DroneEvent::PacketDropped(_))));
+    }
+
+    #[test]
+    /// Checks that scheduled commands are applied before the packets of the same round
+    fn scheduled_command() {
+        let mut simulation = create_chain(0.0, 0);
+
+        simulation.schedule_command(3, 11, DroneCommand::RemoveSender(12));
+        simulation.inject(3, 11, create_fragment(1, vec![1, 11, 12, 21]));
+        assert!(simulation.run_until_idle(10));
+
+        let nack = Packet {
+            pack_type: PacketType::Nack(Nack {
+                fragment_index: 1,
+                nack_type: NackType::ErrorInRouting(12),
+            }),
+            routing_header: SourceRoutingHeader {
+                hop_index: 1,
+                hops: vec![11, 1],
+            },
+            session_id: 1,
+        };
+
+        let deliveries = simulation.deliveries();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].node, 1);
+        assert_eq!(deliveries[0].time, 4);
+        assert_eq!(deliveries[0].packet, nack);
+    }
+
+    #[test]
+    /// Checks that a crashed drone is removed from the simulation
+    fn scheduled_crash() {
+        let mut simulation = create_chain(0.0, 0);
+
+        simulation.schedule_command(0, 12, DroneCommand::Crash);
+        simulation.schedule_command(0, 11, DroneCommand::RemoveSender(12));
+        simulation.run_until(1);
+
+        assert!(simulation.is_crashed(12));
+        assert!(simulation.drone(12).is_none());
+        assert!(simulation.drone(11).is_some());
+        assert!(simulation.is_idle());
+    }
+}
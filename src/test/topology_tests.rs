@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeSet, HashMap};
+    use std::thread;
+    use std::time::Duration;
+    use crossbeam_channel::unbounded;
+    use wg_2024::controller::DroneCommand;
+    use wg_2024::drone::Drone;
+    use wg_2024::network::SourceRoutingHeader;
+    use wg_2024::packet::{FloodRequest, FloodResponse, NodeType, Packet};
+
+    use crate::drone::RustDoIt;
+    use crate::drone::core::{Action, DroneCore};
+    use crate::drone::report::DroneReport;
+
+    fn flood_request() -> Packet {
+        Packet::new_flood_request(
+            SourceRoutingHeader::new(vec![], 0),
+            1,
+            FloodRequest {
+                flood_id: 1,
+                initiator_id: 1,
+                path_trace: vec![(1, NodeType::Client), (12, NodeType::Drone)],
+            },
+        )
+    }
+
+    /// A flood response from server 21 to client 1, relayed by drone 11
+    fn flood_response() -> Packet {
+        Packet::new_flood_response(
+            SourceRoutingHeader::new(vec![21, 13, 11, 1], 2),
+            1,
+            FloodResponse {
+                flood_id: 2,
+                path_trace: vec![
+                    (1, NodeType::Client),
+                    (11, NodeType::Drone),
+                    (13, NodeType::Drone),
+                    (21, NodeType::Server),
+                ],
+            },
+        )
+    }
+
+    #[test]
+    /// The path traces of the relayed floods are accumulated in the topology
+    fn learn_from_floods() {
+        let mut core = DroneCore::new(11, [1, 12, 13], 0.0).with_topology_learning(true);
+        core.handle_packet(flood_request());
+        core.handle_packet(flood_response());
+
+        let topology = core.topology().unwrap();
+        assert_eq!(topology.nodes().collect::<Vec<_>>(), vec![1, 11, 12, 13, 21]);
+        assert_eq!(topology.links().collect::<Vec<_>>(), vec![(1, 11), (1, 12), (11, 12), (11, 13), (13, 21)]);
+        assert_eq!(topology.node_type(21), Some(NodeType::Server));
+        assert_eq!(topology.neighbours(11), BTreeSet::from([1, 12, 13]));
+        assert_eq!(topology.route(12, 21), Some(vec![12, 11, 13, 21]));
+        assert_eq!(topology.route(21, 12), Some(vec![21, 13, 11, 12]));
+    }
+
+    #[test]
+    /// Nothing is learned unless the learning is enabled
+    fn learning_off_by_default() {
+        let mut core = DroneCore::new(11, [1, 12, 13], 0.0);
+        core.handle_packet(flood_request());
+        assert!(core.topology().is_none());
+        assert!(core.topology_report().is_empty());
+    }
+
+    #[test]
+    /// The topology is reported only when it changed since the last report
+    fn report_when_changed() {
+        let mut core = DroneCore::new(11, [1, 12, 13], 0.0).with_topology_learning(true);
+        assert!(core.topology_report().is_empty());
+
+        core.handle_packet(flood_response());
+        let actions = core.topology_report();
+        assert!(matches!(&actions[..], [Action::Report(DroneReport::Topology { drone: 11, .. })]));
+        assert!(core.topology_report().is_empty());
+
+        // the same trace again teaches nothing new
+        core.handle_packet(flood_response());
+        assert!(core.topology_report().is_empty());
+    }
+
+    #[test]
+    /// A running drone reports the topology periodically on its report channel
+    fn periodic_report() {
+        let (controller_send, _event_recv) = unbounded();
+        let (command_send, controller_recv) = unbounded();
+        let (packet_send, packet_recv) = unbounded();
+        let (report_send, report_recv) = unbounded();
+        let (c_send, _c_recv) = unbounded();
+        let (d_send, _d_recv) = unbounded();
+
+        let mut drone = RustDoIt::new(
+            11,
+            controller_send,
+            controller_recv,
+            packet_recv,
+            HashMap::from([(1, c_send), (13, d_send)]),
+            0.0,
+        ).with_reports(report_send).with_topology_reports(Duration::from_millis(10));
+
+        let handle = thread::spawn(move || drone.run());
+        packet_send.send(flood_response()).unwrap();
+
+        let report = report_recv.recv_timeout(Duration::from_secs(1)).unwrap();
+        let DroneReport::Topology { drone, topology } = report else {
+            panic!("Expected a topology report, got {:?}", report);
+        };
+        assert_eq!(drone, 11);
+        assert_eq!(topology.links().count(), 3);
+
+        command_send.send(DroneCommand::Crash).unwrap();
+        handle.join().unwrap();
+    }
+}